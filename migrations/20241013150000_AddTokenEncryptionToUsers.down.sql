@@ -0,0 +1,6 @@
+-- Add down migration script here
+-- Tokens that were already encrypted stay encrypted, those users will need to
+-- log in with Zoom again after rolling back.
+ALTER TABLE Users
+DROP COLUMN token_key_id,
+DROP COLUMN token_data_key;
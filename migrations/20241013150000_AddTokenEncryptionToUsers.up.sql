@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- Rows with a NULL token_key_id still hold plaintext tokens. They get encrypted
+-- by the app on boot since the keys only live in the environment.
+ALTER TABLE Users
+ADD COLUMN token_key_id TEXT NULL,
+ADD COLUMN token_data_key TEXT NULL;
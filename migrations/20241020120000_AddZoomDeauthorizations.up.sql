@@ -0,0 +1,18 @@
+-- Add up migration script here
+CREATE TABLE
+  zoom_deauthorizations (
+    zoom_deauthorization_id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    zoom_user_id TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    deauthorization_time TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL,
+      compliance_acknowledged_at TIMESTAMP
+    WITH
+      TIME ZONE NULL,
+      created_at TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL DEFAULT now ()
+  );
+
+CREATE UNIQUE INDEX ON zoom_deauthorizations (zoom_user_id, deauthorization_time);
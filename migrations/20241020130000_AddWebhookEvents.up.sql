@@ -0,0 +1,21 @@
+-- Add up migration script here
+CREATE TABLE
+  webhook_events (
+    webhook_event_id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    event TEXT NOT NULL,
+    event_ts TIMESTAMP
+    WITH
+      TIME ZONE NULL,
+      dedupe_key TEXT NOT NULL,
+      body JSONB NOT NULL,
+      outcome TEXT NULL,
+      error TEXT NULL,
+      processed_at TIMESTAMP
+    WITH
+      TIME ZONE NULL,
+      created_at TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL DEFAULT now ()
+  );
+
+CREATE UNIQUE INDEX ON webhook_events (dedupe_key);
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- Admins can see the /admin pages. There is no UI to grant it, run:
+-- UPDATE users SET is_admin = true WHERE email = 'someone@example.com';
+ALTER TABLE Users
+ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT false;
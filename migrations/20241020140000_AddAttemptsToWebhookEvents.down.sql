@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE webhook_events
+DROP COLUMN attempts;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE webhook_events
+ADD COLUMN attempts INT NOT NULL DEFAULT 0;
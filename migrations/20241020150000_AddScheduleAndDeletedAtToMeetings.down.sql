@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE Meetings
+DROP COLUMN scheduled_start_time,
+DROP COLUMN scheduled_duration_minutes,
+DROP COLUMN deleted_at;
@@ -0,0 +1,9 @@
+-- Add up migration script here
+ALTER TABLE Meetings
+ADD COLUMN scheduled_start_time TIMESTAMP
+WITH
+  TIME ZONE NULL,
+ADD COLUMN scheduled_duration_minutes INT NULL,
+ADD COLUMN deleted_at TIMESTAMP
+WITH
+  TIME ZONE NULL;
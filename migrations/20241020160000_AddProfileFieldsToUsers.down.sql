@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE Users
+DROP COLUMN email,
+DROP COLUMN timezone,
+DROP COLUMN plan_type;
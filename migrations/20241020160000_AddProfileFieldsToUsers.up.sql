@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TABLE Users
+ADD COLUMN email TEXT NULL,
+ADD COLUMN timezone TEXT NULL,
+ADD COLUMN plan_type INT NULL;
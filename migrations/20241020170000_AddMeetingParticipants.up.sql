@@ -0,0 +1,25 @@
+-- Add up migration script here
+CREATE TABLE
+  meeting_participants (
+    meeting_participant_id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    meeting_id UUID NOT NULL REFERENCES meetings (meeting_id) ON DELETE CASCADE,
+    participant_uuid TEXT NOT NULL,
+    zoom_user_id TEXT NULL,
+    user_name TEXT NOT NULL,
+    email TEXT NULL,
+    join_time TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL,
+      leave_time TIMESTAMP
+    WITH
+      TIME ZONE NULL,
+      leave_reason TEXT NULL,
+      created_at TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL DEFAULT now (),
+      updated_at TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL DEFAULT now ()
+  );
+
+CREATE INDEX ON meeting_participants (meeting_id, participant_uuid);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE Users
+DROP COLUMN end_when_alone_after_minutes;
+
+ALTER TABLE Meetings
+DROP COLUMN ended_reason;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE Users
+ADD COLUMN end_when_alone_after_minutes INT NULL;
+
+ALTER TABLE Meetings
+ADD COLUMN ended_reason TEXT NULL;
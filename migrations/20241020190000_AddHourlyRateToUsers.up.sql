@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE Users
+ADD COLUMN hourly_rate_cents INT NULL;
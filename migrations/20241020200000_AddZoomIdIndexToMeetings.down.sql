@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP INDEX meetings_zoom_id_idx;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+CREATE INDEX ON meetings (zoom_id);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE Meetings
+DROP COLUMN meeting_type,
+DROP COLUMN timezone,
+DROP COLUMN agenda;
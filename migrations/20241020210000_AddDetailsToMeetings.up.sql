@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TABLE Meetings
+ADD COLUMN meeting_type INT NULL,
+ADD COLUMN timezone TEXT NULL,
+ADD COLUMN agenda TEXT NULL;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE meeting_occurrences;
+
+DROP TABLE meeting_series;
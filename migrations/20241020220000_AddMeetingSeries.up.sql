@@ -0,0 +1,35 @@
+-- Add up migration script here
+CREATE TABLE
+  meeting_series (
+    meeting_series_id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    user_id UUID NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    zoom_id TEXT NOT NULL,
+    max_meeting_length_minutes INT NULL,
+    created_at TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL DEFAULT now (),
+      updated_at TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL DEFAULT now ()
+  );
+
+CREATE UNIQUE INDEX ON meeting_series (user_id, zoom_id);
+
+CREATE TABLE
+  meeting_occurrences (
+    meeting_occurrence_id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    meeting_series_id UUID NOT NULL REFERENCES meeting_series (meeting_series_id) ON DELETE CASCADE,
+    occurrence_id TEXT NOT NULL,
+    start_time TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL,
+      max_meeting_length_minutes INT NOT NULL,
+      created_at TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL DEFAULT now (),
+      updated_at TIMESTAMP
+    WITH
+      TIME ZONE NOT NULL DEFAULT now ()
+  );
+
+CREATE UNIQUE INDEX ON meeting_occurrences (meeting_series_id, occurrence_id);
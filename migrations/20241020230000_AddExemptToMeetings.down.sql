@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE meeting_series
+DROP COLUMN exempt;
+
+ALTER TABLE Meetings
+DROP COLUMN exempt;
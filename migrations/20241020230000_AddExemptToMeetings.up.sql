@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE Meetings
+ADD COLUMN exempt BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE meeting_series
+ADD COLUMN exempt BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX meeting_participants_meeting_id_participant_uuid_join_time_idx;
+
+CREATE INDEX ON meeting_participants (meeting_id, participant_uuid);
@@ -0,0 +1,30 @@
+-- Add up migration script here
+-- Retried webhooks could insert the same join twice, keep the one that was closed
+DELETE FROM meeting_participants
+WHERE
+  meeting_participant_id IN (
+    SELECT
+      meeting_participant_id
+    FROM
+      (
+        SELECT
+          meeting_participant_id,
+          row_number() OVER (
+            PARTITION BY
+              meeting_id,
+              participant_uuid,
+              join_time
+            ORDER BY
+              leave_time NULLS LAST,
+              created_at
+          ) AS n
+        FROM
+          meeting_participants
+      ) duplicates
+    WHERE
+      n > 1
+  );
+
+DROP INDEX meeting_participants_meeting_id_participant_uuid_idx;
+
+CREATE UNIQUE INDEX ON meeting_participants (meeting_id, participant_uuid, join_time);
@@ -3,14 +3,21 @@ use std::time::Duration;
 use cja::cron::{CronRegistry, Worker};
 
 use crate::{
-    jobs::{check_live_meetings::CheckLiveMeetings, end_meeting::EndActiveMeetings},
+    jobs::{
+        check_live_meetings::CheckLiveMeetings, end_meeting::EndActiveMeetings,
+        prune_webhook_events::PruneWebhookEvents, reconcile_meetings::ReconcileOpenMeetings,
+        refresh_user_profile::RefreshUserProfiles,
+    },
     AppState,
 };
 
 fn cron_registry() -> CronRegistry<AppState> {
     let mut registry = CronRegistry::new();
     registry.register_job(CheckLiveMeetings, Duration::from_secs(60 * 5));
-    registry.register_job(EndActiveMeetings, Duration::from_secs(30));
+    registry.register_job(EndActiveMeetings, Duration::from_secs(60 * 10));
+    registry.register_job(ReconcileOpenMeetings, Duration::from_secs(60 * 15));
+    registry.register_job(RefreshUserProfiles, Duration::from_secs(60 * 60 * 24));
+    registry.register_job(PruneWebhookEvents, Duration::from_secs(60 * 60 * 24));
     registry
 }
 
@@ -7,7 +7,11 @@ use cja::{
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgPoolOptions, PgPool};
 
-use crate::AppState;
+use crate::{
+    encryption::{Envelope, TokenKind},
+    zoom::{AccessToken, UserZoomClient},
+    AppState,
+};
 
 #[tracing::instrument(err)]
 pub async fn setup_db_pool() -> color_eyre::Result<PgPool> {
@@ -29,6 +33,11 @@ pub async fn setup_db_pool() -> color_eyre::Result<PgPool> {
     Ok(pool)
 }
 
+/// One instance of a Zoom meeting.
+///
+/// `zoom_id` is the meeting number people join with, which recurring meetings and
+/// Personal Meeting Rooms reuse every time they start. `zoom_uuid` is unique to
+/// each instance, so that is what identifies a row.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub(crate) struct DBMeeting {
     pub(crate) meeting_id: Uuid,
@@ -41,9 +50,99 @@ pub(crate) struct DBMeeting {
     pub(crate) max_meeting_length_minutes: Option<i32>,
     pub(crate) created_at: DateTime<Utc>,
     pub(crate) updated_at: DateTime<Utc>,
+    /// When the meeting was scheduled to start, as opposed to when it actually did
+    pub(crate) scheduled_start_time: Option<DateTime<Utc>>,
+    pub(crate) scheduled_duration_minutes: Option<i32>,
+    /// When the host deleted the meeting in Zoom
+    pub(crate) deleted_at: Option<DateTime<Utc>>,
+    /// Why we ended the meeting, see [`crate::jobs::end_meeting::EndedReason`]
+    pub(crate) ended_reason: Option<String>,
+    /// Zoom's meeting `type`: 1 instant, 2 scheduled, 3 and 8 recurring, 4 PMI
+    pub(crate) meeting_type: Option<i32>,
+    pub(crate) timezone: Option<String>,
+    pub(crate) agenda: Option<String>,
+    /// Exempt meetings are never ended by Just Adios
+    pub(crate) exempt: bool,
+}
+
+/// A meeting instance we found out about, from a webhook or from polling
+#[derive(Debug, Clone)]
+pub(crate) struct NewMeeting<'a> {
+    pub(crate) user_id: Uuid,
+    pub(crate) zoom_id: &'a str,
+    pub(crate) zoom_uuid: &'a str,
+    pub(crate) start_time: DateTime<Utc>,
+    pub(crate) topic: Option<&'a str>,
+    pub(crate) scheduled_duration_minutes: Option<i32>,
+}
+
+impl NewMeeting<'_> {
+    /// Inserts the instance, or fills in what we learned if we already have it.
+    ///
+    /// The earliest start time wins, since the webhook has the real one and
+    /// polling can only ever find out about a meeting after it started.
+    ///
+    /// New instances pick up the policy configured ahead of time for their
+    /// `zoom_id`, but a limit already set on the instance itself is never replaced.
+    pub(crate) async fn upsert(&self, db: &PgPool) -> cja::Result<DBMeeting> {
+        let policy =
+            DBMeetingSeries::policy_for(db, self.user_id, self.zoom_id, self.start_time).await?;
+
+        Ok(sqlx::query_as!(
+            DBMeeting,
+            "INSERT INTO meetings (user_id, zoom_id, zoom_uuid, start_time, topic, scheduled_duration_minutes, max_meeting_length_minutes, exempt) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (zoom_uuid) DO UPDATE SET
+                start_time = LEAST(meetings.start_time, EXCLUDED.start_time),
+                topic = COALESCE(EXCLUDED.topic, meetings.topic),
+                scheduled_duration_minutes = COALESCE(EXCLUDED.scheduled_duration_minutes, meetings.scheduled_duration_minutes),
+                max_meeting_length_minutes = COALESCE(meetings.max_meeting_length_minutes, EXCLUDED.max_meeting_length_minutes),
+                updated_at = now()
+            RETURNING *",
+            self.user_id,
+            self.zoom_id,
+            self.zoom_uuid,
+            self.start_time,
+            self.topic,
+            self.scheduled_duration_minutes,
+            policy.max_meeting_length_minutes,
+            policy.exempt
+        )
+        .fetch_one(db)
+        .await?)
+    }
 }
 
 impl DBMeeting {
+    /// Recurring meetings share their `zoom_id` with every other occurrence
+    pub(crate) fn is_recurring(&self) -> bool {
+        matches!(self.meeting_type, Some(3 | 8))
+    }
+
+    pub(crate) async fn find_by_zoom_uuid(
+        db: &PgPool,
+        zoom_uuid: &str,
+    ) -> cja::Result<Option<Self>> {
+        Ok(sqlx::query_as!(
+            DBMeeting,
+            "SELECT * FROM meetings WHERE zoom_uuid = $1",
+            zoom_uuid
+        )
+        .fetch_optional(db)
+        .await?)
+    }
+
+    /// Every join, in the order they happened. Someone who rejoins shows up once
+    /// per join.
+    pub(crate) async fn participants(&self, db: &PgPool) -> cja::Result<Vec<DBMeetingParticipant>> {
+        Ok(sqlx::query_as!(
+            DBMeetingParticipant,
+            "SELECT * FROM meeting_participants WHERE meeting_id = $1 ORDER BY join_time",
+            self.meeting_id
+        )
+        .fetch_all(db)
+        .await?)
+    }
+
     pub(crate) fn is_ended(&self) -> bool {
         self.end_time.is_some()
     }
@@ -73,6 +172,94 @@ impl DBMeeting {
         (max_duration - duration).num_minutes() as i32
     }
 
+    fn end_or_now(&self) -> DateTime<Utc> {
+        self.end_time.unwrap_or_else(chrono::Utc::now)
+    }
+
+    /// Time spent in the meeting by every participant, added together
+    pub(crate) fn participant_minutes(&self, participants: &[DBMeetingParticipant]) -> i64 {
+        self.attended_minutes(participants.iter())
+    }
+
+    /// Time the given joins spent in the meeting, cut off at the meeting's end
+    pub(crate) fn attended_minutes<'a>(
+        &self,
+        joins: impl IntoIterator<Item = &'a DBMeetingParticipant>,
+    ) -> i64 {
+        let end = self.end_or_now();
+
+        joins
+            .into_iter()
+            .map(|p| {
+                (p.leave_time.unwrap_or(end).min(end) - p.join_time).max(chrono::Duration::zero())
+            })
+            .sum::<chrono::Duration>()
+            .num_minutes()
+    }
+
+    /// Participant minutes we cut from the schedule by ending the meeting early.
+    ///
+    /// Only meetings we ended that had a scheduled duration count. Everyone still
+    /// there when we ended it is assumed to have stayed until the scheduled end.
+    pub(crate) fn saved_participant_minutes(
+        &self,
+        participants: &[DBMeetingParticipant],
+    ) -> Option<i64> {
+        self.ended_reason.as_ref()?;
+        let end_time = self.end_time?;
+        let scheduled_end = self.scheduled_start_time.unwrap_or(self.start_time)
+            + chrono::Duration::minutes(self.scheduled_duration_minutes?.into());
+
+        let cut_minutes = (scheduled_end - end_time).num_minutes();
+        if cut_minutes <= 0 {
+            return Some(0);
+        }
+
+        // Zoom's leave events for a meeting we ended trickle in right after the end.
+        // Someone who rejoined just before the end has two rows that both count.
+        let present_at_end = participants
+            .iter()
+            .filter(|p| {
+                p.leave_time
+                    .is_none_or(|leave| leave >= end_time - chrono::Duration::minutes(1))
+            })
+            .map(|p| p.participant_uuid.as_str())
+            .collect::<std::collections::HashSet<_>>()
+            .len() as i64;
+
+        Some(cut_minutes * present_at_end)
+    }
+
+    /// When everyone besides the host left, or `None` if someone else is still here.
+    ///
+    /// Returns `None` when we have no participants at all, since that means we
+    /// aren't getting participant webhooks for this meeting rather than that it
+    /// is empty.
+    pub(crate) fn alone_since(
+        &self,
+        participants: &[DBMeetingParticipant],
+        host: &DBUser,
+    ) -> Option<DateTime<Utc>> {
+        if participants.is_empty() {
+            return None;
+        }
+
+        let mut others = participants
+            .iter()
+            .filter(|p| p.zoom_user_id.as_deref() != Some(host.zoom_id.as_str()))
+            .peekable();
+        if others.peek().is_none() {
+            return Some(self.start_time);
+        }
+
+        others
+            .map(|p| p.leave_time)
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .max()
+            .map(|last_left| last_left.max(self.start_time))
+    }
+
     pub(crate) fn max_duration(&self, user: &DBUser) -> chrono::Duration {
         if let Some(max_meeting_length_minutes) = self.max_meeting_length_minutes {
             return chrono::Duration::minutes(max_meeting_length_minutes as i64);
@@ -88,12 +275,282 @@ impl DBMeeting {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct DBMeetingParticipant {
+    pub(crate) meeting_participant_id: Uuid,
+    pub(crate) meeting_id: Uuid,
+    /// Zoom's id for this participant within the meeting
+    pub(crate) participant_uuid: String,
+    /// The participant's Zoom user id, `None` for guests
+    pub(crate) zoom_user_id: Option<String>,
+    pub(crate) user_name: String,
+    pub(crate) email: Option<String>,
+    pub(crate) join_time: DateTime<Utc>,
+    pub(crate) leave_time: Option<DateTime<Utc>>,
+    pub(crate) leave_reason: Option<String>,
+    pub(crate) created_at: DateTime<Utc>,
+    pub(crate) updated_at: DateTime<Utc>,
+}
+
+impl DBMeetingParticipant {
+    pub(crate) fn is_present(&self) -> bool {
+        self.leave_time.is_none()
+    }
+}
+
+/// Limits set ahead of time for every instance of a meeting, like a recurring
+/// meeting or one that is scheduled but hasn't started yet. Keyed by `zoom_id`
+/// since that is what the instances share.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct DBMeetingSeries {
+    pub(crate) meeting_series_id: Uuid,
+    pub(crate) user_id: Uuid,
+    pub(crate) zoom_id: String,
+    pub(crate) max_meeting_length_minutes: Option<i32>,
+    pub(crate) created_at: DateTime<Utc>,
+    pub(crate) updated_at: DateTime<Utc>,
+    pub(crate) exempt: bool,
+}
+
+/// What a new instance inherits from its [`DBMeetingSeries`]
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct MeetingPolicy {
+    pub(crate) max_meeting_length_minutes: Option<i32>,
+    pub(crate) exempt: bool,
+}
+
+/// A limit for a single occurrence of a series that overrides the series limit
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct DBMeetingOccurrence {
+    pub(crate) meeting_occurrence_id: Uuid,
+    pub(crate) meeting_series_id: Uuid,
+    /// Zoom's `occurrence_id`
+    pub(crate) occurrence_id: String,
+    /// When the occurrence is scheduled to start
+    pub(crate) start_time: DateTime<Utc>,
+    pub(crate) max_meeting_length_minutes: i32,
+    pub(crate) created_at: DateTime<Utc>,
+    pub(crate) updated_at: DateTime<Utc>,
+}
+
+impl DBMeetingSeries {
+    pub(crate) async fn find(
+        db: &PgPool,
+        user_id: Uuid,
+        zoom_id: &str,
+    ) -> cja::Result<Option<Self>> {
+        Ok(sqlx::query_as!(
+            DBMeetingSeries,
+            "SELECT * FROM meeting_series WHERE user_id = $1 AND zoom_id = $2",
+            user_id,
+            zoom_id
+        )
+        .fetch_optional(db)
+        .await?)
+    }
+
+    /// Every series the user has configured, for showing them next to the
+    /// meetings Zoom lists
+    pub(crate) async fn for_user(db: &PgPool, user_id: Uuid) -> cja::Result<Vec<Self>> {
+        Ok(sqlx::query_as!(
+            DBMeetingSeries,
+            "SELECT * FROM meeting_series WHERE user_id = $1",
+            user_id
+        )
+        .fetch_all(db)
+        .await?)
+    }
+
+    pub(crate) async fn upsert(
+        db: &PgPool,
+        user_id: Uuid,
+        zoom_id: &str,
+        policy: MeetingPolicy,
+    ) -> cja::Result<Self> {
+        Ok(sqlx::query_as!(
+            DBMeetingSeries,
+            "INSERT INTO meeting_series (user_id, zoom_id, max_meeting_length_minutes, exempt) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id, zoom_id) DO UPDATE SET
+                max_meeting_length_minutes = EXCLUDED.max_meeting_length_minutes,
+                exempt = EXCLUDED.exempt,
+                updated_at = now()
+            RETURNING *",
+            user_id,
+            zoom_id,
+            policy.max_meeting_length_minutes,
+            policy.exempt
+        )
+        .fetch_one(db)
+        .await?)
+    }
+
+    pub(crate) async fn occurrences(&self, db: &PgPool) -> cja::Result<Vec<DBMeetingOccurrence>> {
+        Ok(sqlx::query_as!(
+            DBMeetingOccurrence,
+            "SELECT * FROM meeting_occurrences WHERE meeting_series_id = $1 ORDER BY start_time",
+            self.meeting_series_id
+        )
+        .fetch_all(db)
+        .await?)
+    }
+
+    /// Overrides the limit for one occurrence, or goes back to the series limit
+    /// when `max_meeting_length_minutes` is `None`
+    pub(crate) async fn set_occurrence_limit(
+        &self,
+        db: &PgPool,
+        occurrence_id: &str,
+        start_time: DateTime<Utc>,
+        max_meeting_length_minutes: Option<i32>,
+    ) -> cja::Result<()> {
+        match max_meeting_length_minutes {
+            Some(max_meeting_length_minutes) => {
+                sqlx::query!(
+                    "INSERT INTO meeting_occurrences (meeting_series_id, occurrence_id, start_time, max_meeting_length_minutes) VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (meeting_series_id, occurrence_id) DO UPDATE SET
+                        start_time = EXCLUDED.start_time,
+                        max_meeting_length_minutes = EXCLUDED.max_meeting_length_minutes,
+                        updated_at = now()",
+                    self.meeting_series_id,
+                    occurrence_id,
+                    start_time,
+                    max_meeting_length_minutes
+                )
+                .execute(db)
+                .await?;
+            }
+            None => {
+                sqlx::query!(
+                    "DELETE FROM meeting_occurrences WHERE meeting_series_id = $1 AND occurrence_id = $2",
+                    self.meeting_series_id,
+                    occurrence_id
+                )
+                .execute(db)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn policy(&self) -> MeetingPolicy {
+        MeetingPolicy {
+            max_meeting_length_minutes: self.max_meeting_length_minutes,
+            exempt: self.exempt,
+        }
+    }
+
+    /// The policy for an instance of the series starting at `start_time`.
+    ///
+    /// Zoom doesn't tell us which occurrence a `meeting.started` belongs to, so an
+    /// override applies to the instance that starts closest to its scheduled time,
+    /// as long as that is within [`OCCURRENCE_MATCH_WINDOW_MINUTES`].
+    pub(crate) async fn policy_for(
+        db: &PgPool,
+        user_id: Uuid,
+        zoom_id: &str,
+        start_time: DateTime<Utc>,
+    ) -> cja::Result<MeetingPolicy> {
+        let window = chrono::Duration::minutes(OCCURRENCE_MATCH_WINDOW_MINUTES);
+
+        let policy = sqlx::query!(
+            r#"SELECT COALESCE(
+                (
+                    SELECT meeting_occurrences.max_meeting_length_minutes FROM meeting_occurrences
+                    WHERE meeting_occurrences.meeting_series_id = meeting_series.meeting_series_id
+                    AND meeting_occurrences.start_time BETWEEN $3 AND $4
+                    ORDER BY abs(extract(epoch FROM meeting_occurrences.start_time - $5))
+                    LIMIT 1
+                ),
+                meeting_series.max_meeting_length_minutes
+            ) AS "max_meeting_length_minutes?",
+            exempt
+            FROM meeting_series WHERE user_id = $1 AND zoom_id = $2"#,
+            user_id,
+            zoom_id,
+            start_time - window,
+            start_time + window,
+            start_time
+        )
+        .fetch_optional(db)
+        .await?;
+
+        Ok(policy
+            .map(|policy| MeetingPolicy {
+                max_meeting_length_minutes: policy.max_meeting_length_minutes,
+                exempt: policy.exempt,
+            })
+            .unwrap_or_default())
+    }
+
+    /// The meeting is gone from Zoom, so nothing will ever pick up its policy again
+    pub(crate) async fn delete_for_zoom_id(
+        db: &PgPool,
+        user_id: Uuid,
+        zoom_id: &str,
+    ) -> cja::Result<u64> {
+        let deleted = sqlx::query!(
+            "DELETE FROM meeting_series WHERE user_id = $1 AND zoom_id = $2",
+            user_id,
+            zoom_id
+        )
+        .execute(db)
+        .await?;
+
+        Ok(deleted.rows_affected())
+    }
+
+    pub(crate) async fn delete_occurrences_for_zoom_id(
+        db: &PgPool,
+        user_id: Uuid,
+        zoom_id: &str,
+        occurrence_ids: &[String],
+    ) -> cja::Result<u64> {
+        let deleted = sqlx::query!(
+            "DELETE FROM meeting_occurrences USING meeting_series
+            WHERE meeting_occurrences.meeting_series_id = meeting_series.meeting_series_id
+            AND meeting_series.user_id = $1
+            AND meeting_series.zoom_id = $2
+            AND meeting_occurrences.occurrence_id = ANY($3)",
+            user_id,
+            zoom_id,
+            occurrence_ids
+        )
+        .execute(db)
+        .await?;
+
+        Ok(deleted.rows_affected())
+    }
+}
+
+/// How far from its scheduled start an instance can start and still pick up
+/// the occurrence's limit
+pub(crate) const OCCURRENCE_MATCH_WINDOW_MINUTES: i64 = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct DBWebhookEvent {
+    pub(crate) webhook_event_id: Uuid,
+    pub(crate) event: String,
+    pub(crate) event_ts: Option<DateTime<Utc>>,
+    /// Hash of the event type, Zoom's `event_ts` and the payload, which stays the
+    /// same when Zoom retries a delivery
+    pub(crate) dedupe_key: String,
+    /// The webhook body exactly as Zoom sent it
+    pub(crate) body: serde_json::Value,
+    pub(crate) outcome: Option<String>,
+    pub(crate) error: Option<String>,
+    pub(crate) processed_at: Option<DateTime<Utc>>,
+    pub(crate) created_at: DateTime<Utc>,
+    pub(crate) attempts: i32,
+}
+
 pub struct DBUser {
     pub(crate) user_id: Uuid,
-    #[allow(dead_code)]
     pub(crate) zoom_id: String,
     pub(crate) display_name: String,
+    /// Encrypted, use [`DBUser::access_token`] to get the usable token
     pub(crate) access_token: String,
+    /// Encrypted, see [`crate::encryption::TokenCipher`]
     pub(crate) refresh_token: String,
     pub(crate) expires_at: DateTime<Utc>,
     pub(crate) default_meeting_length_minutes: Option<i32>,
@@ -102,35 +559,149 @@ pub struct DBUser {
     pub(crate) created_at: DateTime<Utc>,
     #[allow(dead_code)]
     pub(crate) updated_at: DateTime<Utc>,
+    pub(crate) token_key_id: Option<String>,
+    pub(crate) token_data_key: Option<String>,
+    /// Can see the `/admin` pages. Only set by hand in the database, with
+    /// `UPDATE users SET is_admin = true WHERE email = '...'`
+    pub(crate) is_admin: bool,
+    pub(crate) email: Option<String>,
+    /// IANA timezone name from the Zoom profile
+    pub(crate) timezone: Option<String>,
+    /// Zoom's user `type`, see [`DBUser::plan_name`]
+    pub(crate) plan_type: Option<i32>,
+    /// End meetings once the host has been alone (or nobody has been there) for
+    /// this long. `None` turns it off.
+    pub(crate) end_when_alone_after_minutes: Option<i32>,
+    /// Blended hourly cost of a meeting attendee, used for the cost estimates
+    pub(crate) hourly_rate_cents: Option<i32>,
 }
 
 impl DBUser {
+    pub(crate) fn token_envelope(&self) -> Option<Envelope<'_>> {
+        Some(Envelope {
+            key_id: self.token_key_id.as_deref()?,
+            data_key: self.token_data_key.as_deref()?,
+        })
+    }
+
+    fn decrypted_access_token(&self, app_state: &AppState) -> cja::Result<String> {
+        app_state
+            .token_cipher
+            .open(self.token_envelope(), TokenKind::Access, &self.access_token)
+    }
+
+    fn decrypted_refresh_token(&self, app_state: &AppState) -> cja::Result<String> {
+        app_state.token_cipher.open(
+            self.token_envelope(),
+            TokenKind::Refresh,
+            &self.refresh_token,
+        )
+    }
+
     pub(crate) fn is_access_token_expired(&self) -> bool {
         let now_with_buffer = chrono::Utc::now() + chrono::Duration::seconds(60);
 
         self.expires_at < now_with_buffer
     }
 
-    pub(crate) async fn access_token(&self, app_state: &AppState) -> cja::Result<String> {
+    pub(crate) async fn access_token(&self, app_state: &AppState) -> cja::Result<AccessToken> {
         if !self.is_access_token_expired() {
-            return Ok(self.access_token.clone());
+            return Ok(AccessToken::for_user(
+                self.user_id,
+                self.decrypted_access_token(app_state)?,
+            ));
         }
 
-        let token_response =
-            crate::zoom::refresh_access_token(&app_state.zoom, &self.refresh_token).await?;
+        self.refresh_access_token(app_state, None).await
+    }
+
+    /// Gets a new access token after Zoom rejected `rejected` before it expired
+    pub(crate) async fn refresh_rejected_access_token(
+        &self,
+        app_state: &AppState,
+        rejected: &AccessToken,
+    ) -> cja::Result<AccessToken> {
+        self.refresh_access_token(app_state, Some(rejected)).await
+    }
+
+    pub(crate) fn zoom<'a>(&'a self, app_state: &'a AppState) -> UserZoomClient<'a> {
+        UserZoomClient::new(app_state, self)
+    }
+
+    /// Refreshes the access token while holding a lock on the user's row.
+    ///
+    /// Zoom rotates the refresh token on every refresh, so two jobs refreshing at
+    /// once would leave one of them with a dead refresh token. Whoever waited on
+    /// the lock picks up the token the first refresh stored instead.
+    async fn refresh_access_token(
+        &self,
+        app_state: &AppState,
+        rejected: Option<&AccessToken>,
+    ) -> cja::Result<AccessToken> {
+        let mut tx = app_state.db.begin().await?;
+
+        let user = sqlx::query_as!(
+            DBUser,
+            "SELECT * FROM users WHERE user_id = $1 FOR UPDATE",
+            self.user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let access_token = user.decrypted_access_token(app_state)?;
+        let still_valid = match rejected {
+            Some(rejected) => access_token != rejected.as_str(),
+            None => !user.is_access_token_expired(),
+        };
+        if still_valid {
+            tx.commit().await?;
+
+            return Ok(AccessToken::for_user(user.user_id, access_token));
+        }
+
+        let token_response = app_state
+            .zoom_client
+            .refresh_access_token(&app_state.zoom, &user.decrypted_refresh_token(app_state)?)
+            .await?;
 
         let expires_at = chrono::Utc::now() + chrono::Duration::seconds(token_response.expires_in);
+        let sealed = app_state
+            .token_cipher
+            .seal(&token_response.access_token, &token_response.refresh_token)?;
 
         sqlx::query!(
-            "UPDATE users SET access_token = $1, expires_at = $2 WHERE user_id = $3",
-            token_response.access_token,
+            "UPDATE users SET access_token = $1, refresh_token = $2, token_key_id = $3, token_data_key = $4, expires_at = $5, updated_at = now() WHERE user_id = $6",
+            sealed.access_token,
+            sealed.refresh_token,
+            sealed.key_id,
+            sealed.data_key,
             expires_at,
             self.user_id
         )
-        .execute(&app_state.db)
+        .execute(&mut *tx)
         .await?;
 
-        Ok(token_response.access_token)
+        tx.commit().await?;
+
+        Ok(AccessToken::for_user(
+            self.user_id,
+            token_response.access_token,
+        ))
+    }
+
+    /// Estimated cost of the participant minutes, if the user set an hourly rate
+    pub(crate) fn cost_cents(&self, participant_minutes: i64) -> Option<i64> {
+        Some(i64::from(self.hourly_rate_cents?) * participant_minutes / 60)
+    }
+
+    pub(crate) fn plan_name(&self) -> Option<&'static str> {
+        Some(match self.plan_type? {
+            1 => "Basic",
+            2 => "Licensed",
+            4 => "Unassigned",
+            99 => "None",
+            _ => "Unknown",
+        })
     }
 
     pub fn cached_zoom_pic_url(&self) -> Option<String> {
@@ -141,3 +712,304 @@ impl DBUser {
         None
     }
 }
+
+/// Profile fields we copy from Zoom. `None` keeps whatever we have stored, since
+/// `user.updated` webhooks only include the fields that changed.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct ZoomProfileUpdate {
+    pub(crate) display_name: Option<String>,
+    pub(crate) pic_url: Option<String>,
+    pub(crate) email: Option<String>,
+    pub(crate) timezone: Option<String>,
+    #[serde(rename = "type")]
+    pub(crate) plan_type: Option<i32>,
+}
+
+impl ZoomProfileUpdate {
+    pub(crate) async fn save(&self, db: &PgPool, zoom_id: &str) -> cja::Result<u64> {
+        let updated = sqlx::query!(
+            "UPDATE users SET display_name = COALESCE($2, display_name), zoom_pic_url = COALESCE($3, zoom_pic_url), email = COALESCE($4, email), timezone = COALESCE($5, timezone), plan_type = COALESCE($6, plan_type), updated_at = now() WHERE zoom_id = $1",
+            zoom_id,
+            self.display_name,
+            self.pic_url,
+            self.email,
+            self.timezone,
+            self.plan_type
+        )
+        .execute(db)
+        .await?;
+
+        Ok(updated.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST_ZOOM_ID: &str = "host-zoom-id";
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_729_425_600 + minute * 60, 0).unwrap()
+    }
+
+    fn meeting(end: Option<i64>) -> DBMeeting {
+        DBMeeting {
+            meeting_id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            zoom_id: "123".to_string(),
+            zoom_uuid: "uuid==".to_string(),
+            start_time: at(0),
+            end_time: end.map(at),
+            topic: None,
+            max_meeting_length_minutes: None,
+            created_at: at(0),
+            updated_at: at(0),
+            scheduled_start_time: None,
+            scheduled_duration_minutes: None,
+            deleted_at: None,
+            ended_reason: None,
+            meeting_type: Some(2),
+            timezone: None,
+            agenda: None,
+            exempt: false,
+        }
+    }
+
+    fn ended_early(end: i64, scheduled_minutes: i32) -> DBMeeting {
+        DBMeeting {
+            ended_reason: Some("max_duration".to_string()),
+            scheduled_duration_minutes: Some(scheduled_minutes),
+            ..meeting(Some(end))
+        }
+    }
+
+    fn host() -> DBUser {
+        DBUser {
+            user_id: Uuid::nil(),
+            zoom_id: HOST_ZOOM_ID.to_string(),
+            display_name: "Host".to_string(),
+            access_token: String::new(),
+            refresh_token: String::new(),
+            expires_at: at(0),
+            default_meeting_length_minutes: None,
+            zoom_pic_url: None,
+            created_at: at(0),
+            updated_at: at(0),
+            token_key_id: None,
+            token_data_key: None,
+            is_admin: false,
+            email: None,
+            timezone: None,
+            plan_type: None,
+            end_when_alone_after_minutes: Some(5),
+            hourly_rate_cents: None,
+        }
+    }
+
+    fn join(participant_uuid: &str, join: i64, leave: Option<i64>) -> DBMeetingParticipant {
+        DBMeetingParticipant {
+            meeting_participant_id: Uuid::nil(),
+            meeting_id: Uuid::nil(),
+            participant_uuid: participant_uuid.to_string(),
+            zoom_user_id: None,
+            user_name: participant_uuid.to_string(),
+            email: None,
+            join_time: at(join),
+            leave_time: leave.map(at),
+            leave_reason: None,
+            created_at: at(join),
+            updated_at: at(join),
+        }
+    }
+
+    fn host_join(join: i64, leave: Option<i64>) -> DBMeetingParticipant {
+        DBMeetingParticipant {
+            zoom_user_id: Some(HOST_ZOOM_ID.to_string()),
+            ..self::join("host", join, leave)
+        }
+    }
+
+    /// A leave we got without ever seeing the join
+    fn placeholder(participant_uuid: &str, leave: i64) -> DBMeetingParticipant {
+        join(participant_uuid, leave, Some(leave))
+    }
+
+    #[test]
+    fn attended_minutes_are_cut_off_at_the_meeting_end() {
+        let participants = [join("a", 0, None), join("b", 10, Some(45))];
+
+        assert_eq!(
+            meeting(Some(30)).participant_minutes(&participants),
+            30 + 20
+        );
+    }
+
+    #[test]
+    fn attended_minutes_add_up_overlapping_joins() {
+        let participants = [join("a", 0, Some(20)), join("b", 10, Some(30))];
+
+        assert_eq!(
+            meeting(Some(30)).participant_minutes(&participants),
+            20 + 20
+        );
+    }
+
+    #[test]
+    fn attended_minutes_count_each_rejoin_separately() {
+        let participants = [join("a", 0, Some(10)), join("a", 20, Some(30))];
+
+        assert_eq!(meeting(Some(30)).participant_minutes(&participants), 20);
+    }
+
+    #[test]
+    fn placeholders_and_late_joins_add_no_minutes() {
+        let participants = [placeholder("a", 15), join("b", 40, Some(50))];
+
+        assert_eq!(meeting(Some(30)).participant_minutes(&participants), 0);
+    }
+
+    #[test]
+    fn alone_since_ignores_meetings_without_participant_data() {
+        assert_eq!(meeting(None).alone_since(&[], &host()), None);
+    }
+
+    #[test]
+    fn alone_since_the_start_when_only_the_host_joined() {
+        let participants = [host_join(0, Some(5)), host_join(6, None)];
+
+        assert_eq!(
+            meeting(None).alone_since(&participants, &host()),
+            Some(at(0))
+        );
+    }
+
+    #[test]
+    fn not_alone_while_anyone_else_is_here() {
+        let participants = [
+            host_join(0, None),
+            join("a", 1, Some(10)),
+            join("b", 2, None),
+        ];
+
+        assert_eq!(meeting(None).alone_since(&participants, &host()), None);
+    }
+
+    #[test]
+    fn alone_since_the_last_person_left() {
+        let participants = [
+            host_join(0, None),
+            join("a", 1, Some(10)),
+            join("b", 2, Some(20)),
+        ];
+
+        assert_eq!(
+            meeting(None).alone_since(&participants, &host()),
+            Some(at(20))
+        );
+    }
+
+    #[test]
+    fn not_alone_after_someone_rejoins() {
+        let participants = [
+            host_join(0, None),
+            join("a", 1, Some(10)),
+            join("a", 12, None),
+        ];
+
+        assert_eq!(meeting(None).alone_since(&participants, &host()), None);
+    }
+
+    #[test]
+    fn host_rejoining_does_not_count_as_company() {
+        let participants = [
+            host_join(0, Some(10)),
+            join("a", 1, Some(15)),
+            host_join(12, None),
+        ];
+
+        assert_eq!(
+            meeting(None).alone_since(&participants, &host()),
+            Some(at(15))
+        );
+    }
+
+    #[test]
+    fn alone_since_a_placeholder_leave() {
+        let participants = [host_join(0, None), placeholder("a", 8)];
+
+        assert_eq!(
+            meeting(None).alone_since(&participants, &host()),
+            Some(at(8))
+        );
+    }
+
+    #[test]
+    fn alone_since_is_never_before_the_meeting_started() {
+        let participants = [host_join(0, None), join("a", -10, Some(-5))];
+
+        assert_eq!(
+            meeting(None).alone_since(&participants, &host()),
+            Some(at(0))
+        );
+    }
+
+    #[test]
+    fn nothing_saved_for_meetings_we_did_not_end() {
+        let meeting = DBMeeting {
+            ended_reason: None,
+            ..ended_early(30, 60)
+        };
+
+        assert_eq!(
+            meeting.saved_participant_minutes(&[join("a", 0, None)]),
+            None
+        );
+    }
+
+    #[test]
+    fn nothing_saved_without_a_schedule() {
+        let meeting = DBMeeting {
+            scheduled_duration_minutes: None,
+            ..ended_early(30, 60)
+        };
+
+        assert_eq!(
+            meeting.saved_participant_minutes(&[join("a", 0, None)]),
+            None
+        );
+    }
+
+    #[test]
+    fn nothing_saved_when_ended_after_the_schedule() {
+        assert_eq!(
+            ended_early(60, 30).saved_participant_minutes(&[join("a", 0, None)]),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn saved_minutes_count_everyone_present_at_the_end() {
+        let participants = [
+            host_join(0, Some(30)),
+            join("a", 0, None),
+            join("b", 5, Some(10)),
+            placeholder("c", 30),
+        ];
+
+        assert_eq!(
+            ended_early(30, 60).saved_participant_minutes(&participants),
+            Some(30 * 3)
+        );
+    }
+
+    #[test]
+    fn saved_minutes_count_a_rejoin_once() {
+        let participants = [join("a", 0, Some(29)), join("a", 29, None)];
+
+        assert_eq!(
+            ended_early(30, 60).saved_participant_minutes(&participants),
+            Some(30)
+        );
+    }
+}
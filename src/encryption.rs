@@ -0,0 +1,395 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use cja::color_eyre::eyre::{eyre, Context as _};
+
+use crate::{db::DBUser, AppState};
+
+const NONCE_LEN: usize = 12;
+
+/// Envelope encryption for the Zoom tokens we store on `users`.
+///
+/// Every user row gets its own random data key which encrypts the tokens. The data
+/// key is in turn encrypted with one of the keys from `TOKEN_ENCRYPTION_KEYS`, whose
+/// id is stored next to it. Rotating keys only means re-encrypting the data keys.
+///
+/// `TOKEN_ENCRYPTION_KEYS` is a comma separated list of `key_id:hex_encoded_key`
+/// pairs with 32 byte keys. The first key encrypts everything new, the rest are only
+/// kept around to decrypt rows that haven't been rotated yet.
+#[derive(Clone)]
+pub(crate) struct TokenCipher {
+    current_key_id: String,
+    keys: Arc<HashMap<String, Aes256Gcm>>,
+}
+
+impl fmt::Debug for TokenCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenCipher")
+            .field("current_key_id", &self.current_key_id)
+            .field("key_ids", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Which column a ciphertext belongs to. Used as associated data so a ciphertext
+/// can't be moved into a different column and still decrypt.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum TokenKind {
+    Access,
+    Refresh,
+}
+
+impl TokenKind {
+    fn associated_data(self) -> &'static [u8] {
+        match self {
+            Self::Access => b"access_token",
+            Self::Refresh => b"refresh_token",
+        }
+    }
+}
+
+const DATA_KEY_ASSOCIATED_DATA: &[u8] = b"token_data_key";
+
+/// The key id and encrypted data key stored on a user row
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Envelope<'a> {
+    pub(crate) key_id: &'a str,
+    pub(crate) data_key: &'a str,
+}
+
+/// Encrypted tokens, ready to be written to the `users` table
+#[derive(Debug, Clone)]
+pub(crate) struct SealedTokens {
+    pub(crate) key_id: String,
+    pub(crate) data_key: String,
+    pub(crate) access_token: String,
+    pub(crate) refresh_token: String,
+}
+
+impl TokenCipher {
+    pub(crate) fn from_env() -> cja::Result<Self> {
+        let keys =
+            std::env::var("TOKEN_ENCRYPTION_KEYS").context("TOKEN_ENCRYPTION_KEYS not set")?;
+
+        Self::from_key_list(&keys)
+    }
+
+    fn from_key_list(key_list: &str) -> cja::Result<Self> {
+        let mut current_key_id = None;
+        let mut keys = HashMap::new();
+
+        for entry in key_list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (key_id, hex_key) = entry
+                .split_once(':')
+                .ok_or_else(|| eyre!("Token encryption keys must look like key_id:hex_key"))?;
+            let key = hex::decode(hex_key)
+                .with_context(|| format!("Token encryption key {key_id} is not valid hex"))?;
+            let cipher = Aes256Gcm::new_from_slice(&key)
+                .map_err(|_| eyre!("Token encryption key {key_id} must be 32 bytes"))?;
+
+            current_key_id.get_or_insert_with(|| key_id.to_string());
+            keys.insert(key_id.to_string(), cipher);
+        }
+
+        let current_key_id =
+            current_key_id.ok_or_else(|| eyre!("TOKEN_ENCRYPTION_KEYS has no keys"))?;
+
+        Ok(Self {
+            current_key_id,
+            keys: Arc::new(keys),
+        })
+    }
+
+    pub(crate) fn current_key_id(&self) -> &str {
+        &self.current_key_id
+    }
+
+    fn key(&self, key_id: &str) -> cja::Result<&Aes256Gcm> {
+        self.keys
+            .get(key_id)
+            .ok_or_else(|| eyre!("Unknown token encryption key id {key_id}"))
+    }
+
+    /// Encrypts both tokens under a brand new data key
+    pub(crate) fn seal(
+        &self,
+        access_token: &str,
+        refresh_token: &str,
+    ) -> cja::Result<SealedTokens> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let data_cipher = Aes256Gcm::new(&data_key);
+
+        Ok(SealedTokens {
+            key_id: self.current_key_id.clone(),
+            data_key: encrypt(
+                self.key(&self.current_key_id)?,
+                &data_key,
+                DATA_KEY_ASSOCIATED_DATA,
+            )?,
+            access_token: encrypt(
+                &data_cipher,
+                access_token.as_bytes(),
+                TokenKind::Access.associated_data(),
+            )?,
+            refresh_token: encrypt(
+                &data_cipher,
+                refresh_token.as_bytes(),
+                TokenKind::Refresh.associated_data(),
+            )?,
+        })
+    }
+
+    /// Decrypts a token column. Rows without an envelope haven't been encrypted yet
+    /// and are returned as is.
+    pub(crate) fn open(
+        &self,
+        envelope: Option<Envelope<'_>>,
+        kind: TokenKind,
+        value: &str,
+    ) -> cja::Result<String> {
+        let Some(envelope) = envelope else {
+            return Ok(value.to_string());
+        };
+
+        let data_cipher = self.data_cipher(envelope)?;
+        let plaintext = decrypt(&data_cipher, value, kind.associated_data())?;
+
+        String::from_utf8(plaintext).context("Decrypted token is not valid UTF-8")
+    }
+
+    /// Re-encrypts the data key with the current key, the tokens themselves don't change
+    pub(crate) fn rewrap(&self, envelope: Envelope<'_>) -> cja::Result<String> {
+        let data_key = decrypt(
+            self.key(envelope.key_id)?,
+            envelope.data_key,
+            DATA_KEY_ASSOCIATED_DATA,
+        )?;
+
+        encrypt(
+            self.key(&self.current_key_id)?,
+            &data_key,
+            DATA_KEY_ASSOCIATED_DATA,
+        )
+    }
+
+    fn data_cipher(&self, envelope: Envelope<'_>) -> cja::Result<Aes256Gcm> {
+        let data_key = decrypt(
+            self.key(envelope.key_id)?,
+            envelope.data_key,
+            DATA_KEY_ASSOCIATED_DATA,
+        )?;
+
+        Aes256Gcm::new_from_slice(&data_key)
+            .map_err(|_| eyre!("Token data key has the wrong length"))
+    }
+}
+
+/// Encrypts with a random nonce and hex encodes `nonce || ciphertext`
+fn encrypt(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> cja::Result<String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| eyre!("Failed to encrypt token"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+
+    Ok(hex::encode(sealed))
+}
+
+fn decrypt(cipher: &Aes256Gcm, sealed: &str, aad: &[u8]) -> cja::Result<Vec<u8>> {
+    let sealed = hex::decode(sealed).context("Encrypted token is not valid hex")?;
+    if sealed.len() < NONCE_LEN {
+        return Err(eyre!("Encrypted token is too short"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| eyre!("Failed to decrypt token"))
+}
+
+/// Encrypts any tokens still stored in plaintext and moves rows encrypted with an
+/// old key onto the current one. Runs on boot after the schema migrations.
+pub(crate) async fn encrypt_existing_tokens(app_state: &AppState) -> cja::Result<()> {
+    let cipher = &app_state.token_cipher;
+
+    let users = sqlx::query_as!(
+        DBUser,
+        "SELECT * FROM users WHERE token_key_id IS NULL OR token_key_id != $1",
+        cipher.current_key_id()
+    )
+    .fetch_all(&app_state.db)
+    .await?;
+
+    for user in users {
+        match user.token_envelope() {
+            None => {
+                let sealed = cipher.seal(&user.access_token, &user.refresh_token)?;
+
+                sqlx::query!(
+                    "UPDATE users SET access_token = $1, refresh_token = $2, token_key_id = $3, token_data_key = $4 WHERE user_id = $5 AND token_key_id IS NULL",
+                    sealed.access_token,
+                    sealed.refresh_token,
+                    sealed.key_id,
+                    sealed.data_key,
+                    user.user_id
+                )
+                .execute(&app_state.db)
+                .await?;
+            }
+            Some(envelope) => {
+                let data_key = cipher.rewrap(envelope)?;
+
+                sqlx::query!(
+                    "UPDATE users SET token_key_id = $1, token_data_key = $2 WHERE user_id = $3 AND token_key_id = $4",
+                    cipher.current_key_id(),
+                    data_key,
+                    user.user_id,
+                    envelope.key_id
+                )
+                .execute(&app_state.db)
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_KEY: &str = "old:0000000000000000000000000000000000000000000000000000000000000000";
+    const NEW_KEY: &str = "new:1111111111111111111111111111111111111111111111111111111111111111";
+
+    fn envelope(sealed: &SealedTokens) -> Envelope<'_> {
+        Envelope {
+            key_id: &sealed.key_id,
+            data_key: &sealed.data_key,
+        }
+    }
+
+    #[test]
+    fn first_key_is_current() {
+        let cipher = TokenCipher::from_key_list(&format!(" {NEW_KEY} , {OLD_KEY},")).unwrap();
+
+        assert_eq!(cipher.current_key_id(), "new");
+        assert!(cipher.key("old").is_ok());
+        assert!(cipher.key("missing").is_err());
+    }
+
+    #[test]
+    fn invalid_key_lists_are_rejected() {
+        assert!(TokenCipher::from_key_list("").is_err());
+        assert!(TokenCipher::from_key_list(" , ").is_err());
+        assert!(TokenCipher::from_key_list("no-separator").is_err());
+        assert!(TokenCipher::from_key_list("bad:not-hex").is_err());
+        assert!(TokenCipher::from_key_list("short:00112233").is_err());
+    }
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let cipher = TokenCipher::from_key_list(NEW_KEY).unwrap();
+        let sealed = cipher.seal("access", "refresh").unwrap();
+
+        assert_eq!(sealed.key_id, "new");
+        assert_ne!(sealed.access_token, "access");
+        assert_eq!(
+            cipher
+                .open(
+                    Some(envelope(&sealed)),
+                    TokenKind::Access,
+                    &sealed.access_token
+                )
+                .unwrap(),
+            "access"
+        );
+        assert_eq!(
+            cipher
+                .open(
+                    Some(envelope(&sealed)),
+                    TokenKind::Refresh,
+                    &sealed.refresh_token
+                )
+                .unwrap(),
+            "refresh"
+        );
+    }
+
+    #[test]
+    fn plaintext_rows_are_returned_as_is() {
+        let cipher = TokenCipher::from_key_list(NEW_KEY).unwrap();
+
+        assert_eq!(
+            cipher.open(None, TokenKind::Access, "plaintext").unwrap(),
+            "plaintext"
+        );
+    }
+
+    #[test]
+    fn tokens_cannot_be_moved_between_columns() {
+        let cipher = TokenCipher::from_key_list(NEW_KEY).unwrap();
+        let sealed = cipher.seal("access", "refresh").unwrap();
+
+        assert!(cipher
+            .open(
+                Some(envelope(&sealed)),
+                TokenKind::Access,
+                &sealed.refresh_token
+            )
+            .is_err());
+        assert!(cipher
+            .open(
+                Some(envelope(&sealed)),
+                TokenKind::Refresh,
+                &sealed.access_token
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn rewrap_moves_tokens_onto_the_current_key() {
+        let old_cipher = TokenCipher::from_key_list(OLD_KEY).unwrap();
+        let sealed = old_cipher.seal("access", "refresh").unwrap();
+
+        let rotated = TokenCipher::from_key_list(&format!("{NEW_KEY},{OLD_KEY}")).unwrap();
+        let data_key = rotated.rewrap(envelope(&sealed)).unwrap();
+
+        // Once rewrapped the old key is no longer needed
+        let new_cipher = TokenCipher::from_key_list(NEW_KEY).unwrap();
+        let rewrapped = Envelope {
+            key_id: "new",
+            data_key: &data_key,
+        };
+        assert_eq!(
+            new_cipher
+                .open(Some(rewrapped), TokenKind::Access, &sealed.access_token)
+                .unwrap(),
+            "access"
+        );
+        assert!(new_cipher
+            .open(
+                Some(envelope(&sealed)),
+                TokenKind::Access,
+                &sealed.access_token
+            )
+            .is_err());
+    }
+}
@@ -1,7 +1,8 @@
-use cja::jobs::Job;
+use chrono::{DateTime, Utc};
+use cja::{jobs::Job, uuid::Uuid};
 use serde::{Deserialize, Serialize};
 
-use crate::AppState;
+use crate::{zoom::ZoomError, AppState};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 struct NoopJob;
@@ -15,15 +16,98 @@ impl Job<AppState> for NoopJob {
     }
 }
 
+/// Enqueues `job` to run no earlier than `run_at`.
+///
+/// `Job::enqueue` always schedules the job for right now, so this writes the row itself.
+pub(crate) async fn enqueue_at<J: Job<AppState>>(
+    job: J,
+    app_state: &AppState,
+    context: &str,
+    run_at: DateTime<Utc>,
+) -> cja::Result<()> {
+    enqueue_with(job, &app_state.db, context, run_at).await
+}
+
+/// Like [`enqueue_at`], but on any executor so the job can be enqueued in the same
+/// transaction as whatever it is about to work on
+pub(crate) async fn enqueue_with<'e, J: Job<AppState>>(
+    job: J,
+    executor: impl sqlx::PgExecutor<'e>,
+    context: &str,
+    run_at: DateTime<Utc>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        "INSERT INTO jobs (job_id, name, payload, priority, run_at, context) VALUES ($1, $2, $3, 0, $4, $5)",
+        Uuid::new_v4(),
+        J::NAME,
+        serde_json::to_value(job)?,
+        run_at,
+        context,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// How long to wait before trying again when Zoom rate limits us without saying
+/// for how long
+pub(crate) const DEFAULT_RATE_LIMIT_BACKOFF: chrono::Duration = chrono::Duration::seconds(60);
+
+/// Puts `job` back on the queue for when Zoom lets us make requests again, and
+/// passes every error that isn't a rate limit through.
+///
+/// `default_backoff` is used when Zoom didn't say how long to wait.
+pub(crate) async fn reschedule_if_rate_limited<J: Job<AppState>>(
+    job: J,
+    app_state: &AppState,
+    e: cja::color_eyre::Report,
+    default_backoff: chrono::Duration,
+) -> cja::Result<()> {
+    let Some(ZoomError::RateLimited { retry_after }) = e.downcast_ref() else {
+        return Err(e);
+    };
+
+    let backoff = retry_after
+        .and_then(|d| chrono::Duration::from_std(d).ok())
+        .unwrap_or(default_backoff);
+    tracing::info!(job = J::NAME, ?backoff, "Rate limited, trying again later");
+
+    enqueue_at(
+        job,
+        app_state,
+        &format!("{} Rate Limited", J::NAME),
+        Utc::now() + backoff,
+    )
+    .await
+}
+
 pub(crate) mod end_meeting;
 
 pub(crate) mod check_live_meetings;
 
+pub(crate) mod process_zoom_webhook;
+
+pub(crate) mod refresh_user_profile;
+
+pub(crate) mod reconcile_meetings;
+
+pub(crate) mod enrich_meeting;
+
+pub(crate) mod prune_webhook_events;
+
 cja::impl_job_registry!(
     crate::AppState,
     NoopJob,
     end_meeting::EndActiveMeetings,
     end_meeting::EndMeeting,
     check_live_meetings::CheckLiveUserMeetings,
-    check_live_meetings::CheckLiveMeetings
+    check_live_meetings::CheckLiveMeetings,
+    process_zoom_webhook::ProcessZoomWebhookJob,
+    refresh_user_profile::RefreshUserProfile,
+    refresh_user_profile::RefreshUserProfiles,
+    reconcile_meetings::ReconcileUserMeetings,
+    reconcile_meetings::ReconcileOpenMeetings,
+    enrich_meeting::EnrichMeeting,
+    prune_webhook_events::PruneWebhookEvents
 );
@@ -1,15 +1,20 @@
 use chrono::Utc;
 use cja::{jobs::Job, uuid::Uuid};
+use futures::TryStreamExt as _;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    db::DBUser,
-    zoom::{get_meetings, MeetingType},
+    db::{DBMeeting, DBUser, NewMeeting},
+    jobs::{
+        end_meeting::schedule_end_meeting, enrich_meeting::EnrichMeeting,
+        reschedule_if_rate_limited, DEFAULT_RATE_LIMIT_BACKOFF,
+    },
+    zoom::MeetingType,
     AppState,
 };
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub(crate) struct UserId(Uuid);
+pub(crate) struct UserId(pub(crate) Uuid);
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub(crate) struct CheckLiveUserMeetings(UserId);
@@ -24,18 +29,67 @@ impl Job<AppState> for CheckLiveUserMeetings {
             .fetch_one(&app_state.db)
             .await?;
 
-        let meetings = get_meetings(&user.access_token, MeetingType::Live).await?;
-        for meeting in meetings.meetings.iter() {
-            let start_time = Utc::now();
-            sqlx::query!(
-              "INSERT INTO meetings (user_id, zoom_id, zoom_uuid, start_time) VALUES ($1, $2, $3, $4) ON CONFLICT (zoom_id) DO NOTHING",
-              user_id,
-              meeting.id.to_string(),
-              meeting.uuid,
-              start_time,
-            )
-            .execute(&app_state.db)
+        let zoom = user.zoom(&app_state);
+        let meetings = zoom.get_meetings(MeetingType::Live);
+        let mut meetings = std::pin::pin!(meetings);
+        loop {
+            let meeting = match meetings.try_next().await {
+                Ok(Some(meeting)) => meeting,
+                Ok(None) => break,
+                Err(e) => {
+                    return reschedule_if_rate_limited(
+                        self.clone(),
+                        &app_state,
+                        e,
+                        DEFAULT_RATE_LIMIT_BACKOFF,
+                    )
+                    .await
+                }
+            };
+
+            // The webhook already told us about this one, with the real start time
+            if DBMeeting::find_by_zoom_uuid(&app_state.db, &meeting.uuid)
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+
+            let start_time = match zoom.live_meeting_start_time(&meeting).await {
+                Ok(start_time) => start_time.unwrap_or_else(|| {
+                    tracing::info!(
+                        zoom_uuid = meeting.uuid,
+                        "Could not find the meeting's start time, using now"
+                    );
+                    Utc::now()
+                }),
+                Err(e) => {
+                    return reschedule_if_rate_limited(
+                        self.clone(),
+                        &app_state,
+                        e,
+                        DEFAULT_RATE_LIMIT_BACKOFF,
+                    )
+                    .await
+                }
+            };
+
+            let zoom_id = meeting.id.to_string();
+            let inserted = NewMeeting {
+                user_id,
+                zoom_id: &zoom_id,
+                zoom_uuid: &meeting.uuid,
+                start_time,
+                topic: None,
+                scheduled_duration_minutes: meeting.duration.and_then(|d| i32::try_from(d).ok()),
+            }
+            .upsert(&app_state.db)
             .await?;
+
+            schedule_end_meeting(&app_state, &inserted, &user).await?;
+            EnrichMeeting::new(inserted.meeting_id)
+                .enqueue(app_state.clone(), "CheckLiveUserMeetings".to_string())
+                .await?;
         }
 
         Ok(())
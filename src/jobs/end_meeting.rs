@@ -1,18 +1,162 @@
+use chrono::{DateTime, Utc};
 use cja::{jobs::Job, uuid::Uuid};
 use serde::{Deserialize, Serialize};
-use tracing::debug;
+use tracing::{debug, error, info, warn};
 
-use crate::db::{DBMeeting, DBUser};
-use crate::{zoom, AppState};
+use crate::db::{DBMeeting, DBMeetingParticipant, DBUser};
+use crate::jobs::{enqueue_at, reschedule_if_rate_limited};
+use crate::zoom::ZoomError;
+use crate::AppState;
 
 #[derive(Debug, Clone, Deserialize, Serialize, Copy)]
-pub(crate) struct MeetingId(Uuid);
+pub(crate) struct MeetingId(pub(crate) Uuid);
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub(crate) struct EndMeeting(MeetingId);
 
 pub const DEFAULT_MAX_MEETING_LENGTH_MINUTES: i32 = 40;
 
+/// Meetings should end on time, so we try again much sooner than
+/// [`crate::jobs::DEFAULT_RATE_LIMIT_BACKOFF`] when Zoom didn't say how long to wait
+const END_MEETING_RATE_LIMIT_BACKOFF: chrono::Duration = chrono::Duration::seconds(5);
+
+/// Why we ended a meeting, stored in `meetings.ended_reason`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EndedReason {
+    /// Ran past [`DBMeeting::max_duration`]
+    MaxDuration,
+    /// Only the host, or nobody, was left for longer than the user allows
+    Abandoned,
+}
+
+impl EndedReason {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::MaxDuration => "max_duration",
+            Self::Abandoned => "abandoned",
+        }
+    }
+
+    pub(crate) fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "max_duration" => Some(Self::MaxDuration),
+            "abandoned" => Some(Self::Abandoned),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Self::MaxDuration => "it ran past its max length",
+            Self::Abandoned => "nobody besides the host was left in it",
+        }
+    }
+
+    /// Whether the meeting should be ended right now, and why
+    fn evaluate(
+        meeting: &DBMeeting,
+        owner: &DBUser,
+        participants: &[DBMeetingParticipant],
+    ) -> Option<Self> {
+        if meeting.duration() > meeting.max_duration(owner) {
+            return Some(Self::MaxDuration);
+        }
+
+        let alone_deadline = alone_deadline(meeting, owner, participants)?;
+        if Utc::now() > alone_deadline {
+            return Some(Self::Abandoned);
+        }
+
+        None
+    }
+}
+
+/// When the host will have been alone for longer than they allow, if they are alone
+/// and have the setting turned on
+fn alone_deadline(
+    meeting: &DBMeeting,
+    owner: &DBUser,
+    participants: &[DBMeetingParticipant],
+) -> Option<DateTime<Utc>> {
+    let alone_after_minutes = owner.end_when_alone_after_minutes?;
+    let alone_since = meeting.alone_since(participants, owner)?;
+
+    Some(alone_since + chrono::Duration::minutes(alone_after_minutes.into()))
+}
+
+/// The earliest time [`EndedReason::evaluate`] could decide to end the meeting
+fn next_check_at(
+    meeting: &DBMeeting,
+    owner: &DBUser,
+    participants: &[DBMeetingParticipant],
+) -> DateTime<Utc> {
+    let deadline = meeting.start_time + meeting.max_duration(owner);
+
+    match alone_deadline(meeting, owner, participants) {
+        Some(alone_deadline) => deadline.min(alone_deadline),
+        None => deadline,
+    }
+}
+
+/// Replaces any pending [`EndMeeting`] jobs for the meeting with one that runs when
+/// the meeting is next due to be ended.
+///
+/// Call this whenever something that goes into the deadline changes, like the
+/// meeting's max length, the owner's settings or who is in the meeting.
+pub(crate) async fn schedule_end_meeting(
+    app_state: &AppState,
+    meeting: &DBMeeting,
+    owner: &DBUser,
+) -> cja::Result<()> {
+    if meeting.is_ended() {
+        return Ok(());
+    }
+
+    if meeting.exempt {
+        return cancel_scheduled(app_state, meeting).await;
+    }
+
+    let participants = meeting.participants(&app_state.db).await?;
+
+    reschedule(
+        app_state,
+        meeting,
+        next_check_at(meeting, owner, &participants),
+    )
+    .await
+}
+
+async fn cancel_scheduled(app_state: &AppState, meeting: &DBMeeting) -> cja::Result<()> {
+    // Jobs that are already running are locked, so this only clears queued ones
+    sqlx::query!(
+        "DELETE FROM jobs WHERE name = $1 AND payload #>> '{}' = $2 AND locked_at IS NULL",
+        EndMeeting::NAME,
+        meeting.meeting_id.to_string()
+    )
+    .execute(&app_state.db)
+    .await?;
+
+    Ok(())
+}
+
+async fn reschedule(
+    app_state: &AppState,
+    meeting: &DBMeeting,
+    run_at: DateTime<Utc>,
+) -> cja::Result<()> {
+    cancel_scheduled(app_state, meeting).await?;
+
+    debug!(meeting_id = %meeting.meeting_id, %run_at, "Scheduling EndMeeting");
+
+    enqueue_at(
+        EndMeeting(MeetingId(meeting.meeting_id)),
+        app_state,
+        "EndMeeting Scheduled",
+        run_at,
+    )
+    .await
+}
+
 #[async_trait::async_trait]
 impl Job<AppState> for EndMeeting {
     const NAME: &'static str = "EndMeeting";
@@ -40,22 +184,87 @@ impl Job<AppState> for EndMeeting {
             return Ok(());
         }
 
-        let duration = meeting.duration();
-        let max_duration = meeting.max_duration(&owner);
+        if meeting.exempt {
+            debug!("Meeting is exempt from limits");
+            return Ok(());
+        }
+
+        let participants = meeting.participants(&app_state.db).await?;
+        let Some(reason) = EndedReason::evaluate(&meeting, &owner, &participants) else {
+            debug!("Meeting doesn't need ending yet");
+            return reschedule(
+                &app_state,
+                &meeting,
+                next_check_at(&meeting, &owner, &participants),
+            )
+            .await;
+        };
+
+        debug!(?reason, "Going to end the meeting");
+
+        match owner.zoom(&app_state).adios(&meeting.zoom_id).await {
+            Ok(()) => {
+                sqlx::query!(
+                    "UPDATE meetings SET ended_reason = $1, updated_at = now() WHERE meeting_id = $2",
+                    reason.as_str(),
+                    meeting.meeting_id
+                )
+                .execute(&app_state.db)
+                .await?;
+            }
+            Err(e) => match e.downcast_ref::<ZoomError>() {
+                Some(
+                    ZoomError::MeetingNotFound { .. } | ZoomError::MeetingNotInProgress { .. },
+                ) => {
+                    info!("Meeting is no longer running on Zoom, marking it as ended: {e}");
 
-        if duration > max_duration {
-            debug!("Meeting duration is long enough, going to end it");
+                    // We probably missed `meeting.ended`, so ask Zoom when it really
+                    // ended instead of using whenever this job happened to run
+                    let end_time = match owner
+                        .zoom(&app_state)
+                        .get_past_meeting(&meeting.zoom_uuid)
+                        .await
+                    {
+                        Ok(past_meeting) => past_meeting.end_time,
+                        Err(e) => {
+                            warn!("Could not look up past meeting, using the current time: {e}");
+                            None
+                        }
+                    };
 
-            let access_token = owner.access_token(&app_state).await?;
-            zoom::adios(&meeting.zoom_id, &access_token).await?;
-        } else {
-            debug!("Meeting duration is too short");
+                    sqlx::query!(
+                        "UPDATE meetings SET end_time = COALESCE($1, now()), updated_at = now() WHERE meeting_id = $2 AND end_time IS NULL",
+                        end_time,
+                        meeting.meeting_id
+                    )
+                    .execute(&app_state.db)
+                    .await?;
+                }
+                Some(ZoomError::RateLimited { .. }) => {
+                    reschedule_if_rate_limited(
+                        self.clone(),
+                        &app_state,
+                        e,
+                        END_MEETING_RATE_LIMIT_BACKOFF,
+                    )
+                    .await?;
+                }
+                Some(ZoomError::TokenRevoked { .. }) => {
+                    warn!(user_id = %owner.user_id, "Zoom token revoked, user needs to log in again: {e}");
+                }
+                Some(zoom_error) if !zoom_error.is_retryable() => {
+                    error!("Could not end meeting and retrying won't help: {e}");
+                }
+                _ => return Err(e),
+            },
         }
 
         Ok(())
     }
 }
 
+/// Safety net for meetings whose scheduled [`EndMeeting`] job went missing. Each
+/// [`EndMeeting`] reschedules itself if the meeting isn't due yet.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub(crate) struct EndActiveMeetings;
 
@@ -0,0 +1,116 @@
+use cja::{jobs::Job, uuid::Uuid};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{DBMeeting, DBUser, OCCURRENCE_MATCH_WINDOW_MINUTES},
+    jobs::{end_meeting::MeetingId, reschedule_if_rate_limited, DEFAULT_RATE_LIMIT_BACKOFF},
+    zoom::ZoomError,
+    AppState,
+};
+
+/// Fills in the details Zoom only gives us from the meeting endpoint, like the
+/// topic for meetings we found by polling
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct EnrichMeeting(MeetingId);
+
+impl EnrichMeeting {
+    pub(crate) fn new(meeting_id: Uuid) -> Self {
+        Self(MeetingId(meeting_id))
+    }
+}
+
+#[async_trait::async_trait]
+impl Job<AppState> for EnrichMeeting {
+    const NAME: &'static str = "EnrichMeeting";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        let meeting = sqlx::query_as!(
+            DBMeeting,
+            "SELECT * FROM meetings WHERE meeting_id = $1",
+            self.0 .0
+        )
+        .fetch_one(&app_state.db)
+        .await?;
+
+        let owner = sqlx::query_as!(
+            DBUser,
+            "SELECT * FROM users WHERE user_id = $1",
+            meeting.user_id
+        )
+        .fetch_one(&app_state.db)
+        .await?;
+
+        let details = match owner
+            .zoom(&app_state)
+            .get_meeting_details(&meeting.zoom_id)
+            .await
+        {
+            Ok(details) => details,
+            Err(e) => {
+                return match e.downcast_ref::<ZoomError>() {
+                    Some(ZoomError::MeetingNotFound { .. }) => {
+                        tracing::info!(meeting_id = %meeting.meeting_id, "Meeting no longer exists in Zoom, nothing to enrich");
+                        Ok(())
+                    }
+                    Some(ZoomError::RateLimited { .. }) => {
+                        reschedule_if_rate_limited(
+                            self.clone(),
+                            &app_state,
+                            e,
+                            DEFAULT_RATE_LIMIT_BACKOFF,
+                        )
+                        .await
+                    }
+                    _ => Err(e),
+                };
+            }
+        };
+
+        // A recurring meeting's own start time is its first occurrence, so use the
+        // occurrence this instance was scheduled as
+        let window = chrono::Duration::minutes(OCCURRENCE_MATCH_WINDOW_MINUTES);
+        let occurrence = details
+            .occurrences
+            .iter()
+            .flatten()
+            .filter(|o| (o.start_time - meeting.start_time).abs() <= window)
+            .min_by_key(|o| (o.start_time - meeting.start_time).abs());
+
+        // Instant meetings, PMIs and recurring meetings with no fixed time report
+        // when they were created or first used, and Zoom's default duration, which
+        // aren't a schedule the user set
+        let has_schedule = occurrence.is_some() || matches!(details.r#type, 2 | 8);
+        let (scheduled_start_time, scheduled_duration) = match (occurrence, details.r#type) {
+            (Some(occurrence), _) => (Some(occurrence.start_time), Some(occurrence.duration)),
+            (None, 2) => (details.start_time, details.duration),
+            (None, 8) => (None, details.duration),
+            (None, _) => (None, None),
+        };
+
+        sqlx::query!(
+            "UPDATE meetings SET
+                topic = COALESCE($1, topic),
+                meeting_type = $2,
+                scheduled_start_time = COALESCE(scheduled_start_time, $3),
+                -- The duration we stored when the meeting started is Zoom's default
+                -- for meetings without a schedule
+                scheduled_duration_minutes = CASE WHEN $8 THEN COALESCE($4, scheduled_duration_minutes) END,
+                timezone = COALESCE($5, timezone),
+                agenda = COALESCE($6, agenda),
+                updated_at = now()
+            WHERE meeting_id = $7",
+            details.topic,
+            i32::try_from(details.r#type).ok(),
+            scheduled_start_time,
+            scheduled_duration.and_then(|d| i32::try_from(d).ok()),
+            details.timezone,
+            details.agenda.filter(|agenda| !agenda.is_empty()),
+            meeting.meeting_id,
+            has_schedule
+        )
+        .execute(&app_state.db)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use cja::{jobs::Job, uuid::Uuid};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::jobs::enqueue_at;
+use crate::routes::webhooks::{events, process_stored_event};
+use crate::AppState;
+
+/// Attempts before we give up on a webhook and leave it as failed for an admin to
+/// look at and replay
+const MAX_ATTEMPTS: i32 = 5;
+
+/// Doubled after every failed attempt
+const BASE_RETRY_BACKOFF: chrono::Duration = chrono::Duration::seconds(30);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct ProcessZoomWebhookJob(Uuid);
+
+impl ProcessZoomWebhookJob {
+    pub(crate) fn new(webhook_event_id: Uuid) -> Self {
+        Self(webhook_event_id)
+    }
+}
+
+#[async_trait::async_trait]
+impl Job<AppState> for ProcessZoomWebhookJob {
+    const NAME: &'static str = "ProcessZoomWebhookJob";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        let webhook_event_id = self.0;
+        let attempts = events::start_attempt(&app_state, webhook_event_id).await?;
+
+        let e = match process_stored_event(&app_state, webhook_event_id).await {
+            Ok(outcome) => {
+                return events::record_outcome(
+                    &app_state,
+                    webhook_event_id,
+                    outcome.as_str(),
+                    None,
+                )
+                .await;
+            }
+            Err(e) => e,
+        };
+
+        if attempts >= MAX_ATTEMPTS {
+            error!(%webhook_event_id, attempts, "Giving up on zoom webhook: {e:?}");
+
+            return events::record_outcome(
+                &app_state,
+                webhook_event_id,
+                "failed",
+                Some(format!("{e:#}")),
+            )
+            .await;
+        }
+
+        let backoff = BASE_RETRY_BACKOFF * 2i32.pow(attempts as u32 - 1);
+        warn!(%webhook_event_id, attempts, ?backoff, "Zoom webhook failed, retrying: {e:?}");
+
+        events::record_outcome(
+            &app_state,
+            webhook_event_id,
+            "retrying",
+            Some(format!("{e:#}")),
+        )
+        .await?;
+        enqueue_at(
+            self.clone(),
+            &app_state,
+            "ProcessZoomWebhookJob Retry",
+            chrono::Utc::now() + backoff,
+        )
+        .await
+    }
+}
@@ -0,0 +1,23 @@
+use cja::jobs::Job;
+use serde::{Deserialize, Serialize};
+
+use crate::{routes::webhooks::events, AppState};
+
+/// How long we keep webhook bodies around for debugging and replays. They contain
+/// names and emails of meeting participants, so not forever.
+const WEBHOOK_EVENT_RETENTION: chrono::Duration = chrono::Duration::days(30);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct PruneWebhookEvents;
+
+#[async_trait::async_trait]
+impl Job<AppState> for PruneWebhookEvents {
+    const NAME: &'static str = "PruneWebhookEvents";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        let pruned = events::prune(&app_state, WEBHOOK_EVENT_RETENTION).await?;
+        tracing::info!("Pruned {pruned} old webhook events");
+
+        Ok(())
+    }
+}
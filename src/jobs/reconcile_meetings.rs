@@ -0,0 +1,123 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
+use cja::jobs::Job;
+use futures::TryStreamExt as _;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{DBMeeting, DBUser},
+    jobs::{check_live_meetings::UserId, reschedule_if_rate_limited, DEFAULT_RATE_LIMIT_BACKOFF},
+    zoom::{MeetingType, ZoomError},
+    AppState,
+};
+
+/// Meetings this new might not show up as live in Zoom's API yet
+const MIN_MEETING_AGE: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Closes the user's open meetings that Zoom no longer lists as live, for when we
+/// missed the `meeting.ended` webhook
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct ReconcileUserMeetings(UserId);
+
+impl ReconcileUserMeetings {
+    async fn reconcile(&self, app_state: &AppState, user: &DBUser) -> cja::Result<()> {
+        let open_meetings = sqlx::query_as!(
+            DBMeeting,
+            "SELECT * FROM meetings WHERE user_id = $1 AND end_time IS NULL AND created_at < $2",
+            user.user_id,
+            Utc::now() - MIN_MEETING_AGE
+        )
+        .fetch_all(&app_state.db)
+        .await?;
+        if open_meetings.is_empty() {
+            return Ok(());
+        }
+
+        let zoom = user.zoom(app_state);
+        let live_uuids: HashSet<String> = zoom
+            .get_meetings(MeetingType::Live)
+            .map_ok(|meeting| meeting.uuid)
+            .try_collect()
+            .await?;
+
+        for meeting in open_meetings {
+            if live_uuids.contains(&meeting.zoom_uuid) {
+                continue;
+            }
+
+            let end_time = match zoom.get_past_meeting(&meeting.zoom_uuid).await {
+                Ok(past_meeting) => past_meeting.end_time,
+                // Zoom already told us the meeting isn't live, so only give up on
+                // closing it when asking again could get us the real end time
+                Err(e) => match e.downcast_ref::<ZoomError>() {
+                    Some(zoom_error) if !zoom_error.is_retryable() => {
+                        tracing::warn!(
+                            meeting_id = %meeting.meeting_id,
+                            "Could not look up past meeting, closing it now: {e}"
+                        );
+                        None
+                    }
+                    _ => return Err(e),
+                },
+            };
+
+            tracing::info!(
+                meeting_id = %meeting.meeting_id,
+                ?end_time,
+                "Meeting is no longer live in Zoom, closing it"
+            );
+
+            sqlx::query!(
+                "UPDATE meetings SET end_time = COALESCE($1, now()), updated_at = now() WHERE meeting_id = $2 AND end_time IS NULL",
+                end_time,
+                meeting.meeting_id
+            )
+            .execute(&app_state.db)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Job<AppState> for ReconcileUserMeetings {
+    const NAME: &'static str = "ReconcileUserMeetings";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        let user_id = self.0 .0;
+        let user = sqlx::query_as!(DBUser, "SELECT * FROM users WHERE user_id = $1", user_id)
+            .fetch_one(&app_state.db)
+            .await?;
+
+        let Err(e) = self.reconcile(&app_state, &user).await else {
+            return Ok(());
+        };
+
+        reschedule_if_rate_limited(self.clone(), &app_state, e, DEFAULT_RATE_LIMIT_BACKOFF).await
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct ReconcileOpenMeetings;
+
+#[async_trait::async_trait]
+impl Job<AppState> for ReconcileOpenMeetings {
+    const NAME: &'static str = "ReconcileOpenMeetings";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        let user_ids =
+            sqlx::query_scalar!("SELECT DISTINCT user_id FROM meetings WHERE end_time IS NULL")
+                .fetch_all(&app_state.db)
+                .await?;
+
+        for user_id in user_ids {
+            ReconcileUserMeetings(UserId(user_id))
+                .enqueue(app_state.clone(), "ReconcileOpenMeetings Loop".to_string())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use cja::jobs::Job;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{DBUser, ZoomProfileUpdate},
+    jobs::{check_live_meetings::UserId, reschedule_if_rate_limited, DEFAULT_RATE_LIMIT_BACKOFF},
+    AppState,
+};
+
+/// Pulls the user's profile from Zoom, in case we missed a `user.updated` webhook
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct RefreshUserProfile(UserId);
+
+#[async_trait::async_trait]
+impl Job<AppState> for RefreshUserProfile {
+    const NAME: &'static str = "RefreshUserProfile";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        let user = sqlx::query_as!(DBUser, "SELECT * FROM users WHERE user_id = $1", self.0 .0)
+            .fetch_one(&app_state.db)
+            .await?;
+
+        let zoom_user = match user.zoom(&app_state).get_current_user().await {
+            Ok(zoom_user) => zoom_user,
+            Err(e) => {
+                return reschedule_if_rate_limited(
+                    self.clone(),
+                    &app_state,
+                    e,
+                    DEFAULT_RATE_LIMIT_BACKOFF,
+                )
+                .await
+            }
+        };
+
+        ZoomProfileUpdate::from(zoom_user)
+            .save(&app_state.db, &user.zoom_id)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct RefreshUserProfiles;
+
+#[async_trait::async_trait]
+impl Job<AppState> for RefreshUserProfiles {
+    const NAME: &'static str = "RefreshUserProfiles";
+
+    async fn run(&self, app_state: AppState) -> cja::Result<()> {
+        let users = sqlx::query_as!(DBUser, "SELECT * FROM users")
+            .fetch_all(&app_state.db)
+            .await?;
+
+        for user in users.iter() {
+            RefreshUserProfile(UserId(user.user_id))
+                .enqueue(app_state.clone(), "RefreshUserProfiles Loop".to_string())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
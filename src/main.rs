@@ -4,6 +4,7 @@ use tracing::info;
 
 mod cron;
 mod db;
+mod encryption;
 mod jobs;
 mod routes;
 
@@ -16,6 +17,8 @@ struct AppState {
     db: sqlx::PgPool,
     cookie_key: cja::server::cookies::CookieKey,
     zoom: ZoomState,
+    zoom_client: zoom::ZoomClient,
+    token_cipher: encryption::TokenCipher,
     base_url: String,
 }
 
@@ -23,7 +26,9 @@ struct AppState {
 struct ZoomState {
     client_id: String,
     client_secret: String,
-    secret_token: String,
+    /// Webhook secret tokens, the first one is current and the rest are only
+    /// accepted while rotating
+    secret_tokens: Vec<String>,
 }
 
 impl ZoomState {
@@ -31,15 +36,27 @@ impl ZoomState {
         let client_id = std::env::var("ZOOM_CLIENT_ID").context("ZOOM_CLIENT_ID not set")?;
         let client_secret =
             std::env::var("ZOOM_CLIENT_SECRET").context("ZOOM_CLIENT_SECRET not set")?;
-        let secret_token =
-            std::env::var("ZOOM_SECRET_TOKEN").context("ZOOM_SECRET_TOKEN not set")?;
+        let secret_tokens: Vec<String> = std::env::var("ZOOM_SECRET_TOKEN")
+            .context("ZOOM_SECRET_TOKEN not set")?
+            .split(',')
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .map(str::to_string)
+            .collect();
+        if secret_tokens.is_empty() {
+            return Err(cja::color_eyre::eyre::eyre!("ZOOM_SECRET_TOKEN is empty"));
+        }
 
         Ok(Self {
             client_id,
             client_secret,
-            secret_token,
+            secret_tokens,
         })
     }
+
+    fn current_secret_token(&self) -> &str {
+        &self.secret_tokens[0]
+    }
 }
 
 impl AS for AppState {
@@ -81,14 +98,22 @@ async fn _main() -> cja::Result<()> {
 
     let base_url = std::env::var("BASE_URL").context("BASE_URL not set")?;
     let zoom = ZoomState::from_env()?;
+    let zoom_client = zoom::ZoomClient::from_env()?;
+    let token_cipher = encryption::TokenCipher::from_env()?;
 
     let app_state = AppState {
         db: db_pool,
         cookie_key,
         zoom,
+        zoom_client,
+        token_cipher,
         base_url,
     };
 
+    encryption::encrypt_existing_tokens(&app_state)
+        .await
+        .context("Failed to encrypt existing Zoom tokens")?;
+
     let app = routes::routes(app_state.clone());
 
     info!("Spawning Tasks");
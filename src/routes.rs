@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Path, Query, State},
     response::{IntoResponse, Redirect, Response},
@@ -6,17 +8,26 @@ use axum::{
 };
 use chrono::Utc;
 use cja::{app_state::AppState as _, server::session::DBSession};
+use futures::TryStreamExt as _;
 use maud::{html, Render};
 use reqwest::StatusCode;
 use serde::{Deserialize, Deserializer, Serialize};
 use tower_cookies::Cookies;
 
-mod webhooks;
+mod admin;
+mod series;
+mod upcoming;
+pub(crate) mod webhooks;
 
 use crate::{
-    db::{DBMeeting, DBUser},
-    views::Section,
-    zoom::{get_meetings, MeetingType},
+    db::{DBMeeting, DBMeetingParticipant, DBUser},
+    jobs::end_meeting::{schedule_end_meeting, EndedReason},
+    views::{
+        attendance::{AttendanceTable, AttendanceTimeline},
+        cost::Dollars,
+        Section,
+    },
+    zoom::{AccessToken, MeetingType},
     AppState,
 };
 
@@ -27,21 +38,33 @@ pub fn routes(app_state: AppState) -> axum::Router {
         .route("/meetings", get(meetings))
         .route("/meetings/:meeting_id", get(meeting))
         .route("/meetings/:meeting_id", post(edit_meeting))
+        .route("/series/:zoom_id", get(series::series))
+        .route("/series/:zoom_id", post(series::edit_series))
+        .route(
+            "/series/:zoom_id/occurrences/:occurrence_id",
+            post(series::edit_occurrence),
+        )
+        .route("/upcoming", get(upcoming::upcoming))
+        .route("/upcoming/:zoom_id", post(upcoming::edit_upcoming))
         .route("/settings", get(settings))
         .route("/settings/edit", get(edit_settings))
         .route("/settings/edit", post(update_settings))
         .route("/debug", get(live_api_debug))
         .route("/oauth/zoom", get(zoom_oauth))
         .route("/webhooks/zoom", post(webhooks::zoom_webhook))
+        .route("/admin/webhook_events", get(admin::webhook_events))
+        .route(
+            "/admin/webhook_events/:webhook_event_id/replay",
+            post(admin::replay_webhook_event),
+        )
         .with_state(app_state)
 }
 
 async fn login(State(state): State<AppState>) -> impl IntoResponse {
     let zoom_redirect_uri = state.zoom_redirect_url();
-    let client_id = &state.zoom.client_id;
-    let zoom_auth_url = format!(
-        "https://zoom.us/oauth/authorize?response_type=code&client_id={client_id}&redirect_uri={zoom_redirect_uri}",
-    );
+    let zoom_auth_url = state
+        .zoom_client
+        .authorize_url(&state.zoom.client_id, &zoom_redirect_uri);
 
     Redirect::to(&zoom_auth_url).into_response()
 }
@@ -62,28 +85,36 @@ async fn live_api_debug(
         (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch user").into_response()
     })?;
 
-    let access_token = user.access_token(&state).await.map_err(|e| {
-        tracing::error!("Failed to get access token: {e:?}");
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to get access token",
-        )
-            .into_response()
-    })?;
+    let zoom = user.zoom(&state);
 
-    let meetings = get_meetings(&access_token, MeetingType::Live)
+    let meetings: Vec<_> = zoom
+        .get_meetings(MeetingType::Live)
+        .try_collect()
         .await
         .map_err(|e| {
             tracing::error!("Failed to get meetings: {e:?}");
             (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get meetings").into_response()
         })?;
 
-    let channels = crate::zoom::get_chat_channels(&access_token)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to get channels: {e:?}");
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get channels").into_response()
+    let mut live_durations = vec![];
+    for meeting in &meetings {
+        let start_time = zoom.live_meeting_start_time(meeting).await.map_err(|e| {
+            tracing::error!("Failed to get meeting start time: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to get meeting start time",
+            )
+                .into_response()
         })?;
+        live_durations.push(start_time.map(|start_time| (Utc::now() - start_time).num_seconds()));
+    }
+
+    let channels = zoom.get_chat_channels().await.map_err(|e| {
+        tracing::error!("Failed to get channels: {e:?}");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get channels").into_response()
+    })?;
+
+    let rate_limit_stats = state.zoom_client.rate_limit_stats();
 
     Ok(html! {
         h1 { "Meetings" }
@@ -93,20 +124,28 @@ async fn live_api_debug(
         }
 
         p {
-          "Total meetings: " (meetings.total_records)
+          "Total meetings: " (meetings.len())
         }
 
         ul {
-          @for meeting in meetings.meetings {
+          @for (meeting, live_duration) in meetings.iter().zip(live_durations) {
             li {
               (format!("{meeting:?}"))
-              (meeting.live_duration().unwrap_or(-1))
+              (live_duration.unwrap_or(-1))
             }
           }
         }
 
         h2 { "Channels" }
         p { (format!("{channels:#?}")) }
+
+        h2 { "Zoom Rate Limits" }
+        ul {
+          li { "Requests: " (rate_limit_stats.requests) }
+          li { "Throttled: " (rate_limit_stats.throttled) }
+          li { "Rejected locally: " (rate_limit_stats.rejected) }
+          li { "429s from Zoom: " (rate_limit_stats.rate_limited) }
+        }
     })
 }
 
@@ -122,6 +161,9 @@ impl Render for MeetingLink {
 
         html! {
             a href=(format!("/meetings/{}", self.0.meeting_id)) { (name) }
+            @if meeting.deleted_at.is_some() {
+                " (deleted in Zoom)"
+            }
         }
     }
 }
@@ -158,6 +200,29 @@ async fn meetings(
             .into_response()
     })?;
 
+    let participants = sqlx::query_as!(
+        DBMeetingParticipant,
+        "SELECT meeting_participants.* FROM meeting_participants JOIN meetings USING (meeting_id) WHERE meetings.user_id = $1",
+        session.user_id,
+    )
+    .fetch_all(state.db())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch participants: {e:?}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to fetch participants",
+        )
+            .into_response()
+    })?;
+    let mut participants_by_meeting: HashMap<_, Vec<_>> = HashMap::new();
+    for participant in participants {
+        participants_by_meeting
+            .entry(participant.meeting_id)
+            .or_default()
+            .push(participant);
+    }
+
     let mut meetings = meetings;
     meetings.sort_by_key(|m| m.start_time);
     meetings.reverse();
@@ -165,25 +230,56 @@ async fn meetings(
 
     let (current_meetings, ended_meetings): (Vec<_>, Vec<_>) = meetings
         .into_iter()
-        .map(MeetingLink)
-        .partition(|m| !m.0.is_ended());
+        .map(|meeting| {
+            let participants = participants_by_meeting
+                .get(&meeting.meeting_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let cost = user.cost_cents(meeting.participant_minutes(participants));
+            let saved = meeting
+                .saved_participant_minutes(participants)
+                .and_then(|minutes| user.cost_cents(minutes));
+
+            (MeetingLink(meeting), cost, saved)
+        })
+        .partition(|(m, _, _)| !m.0.is_ended());
+
+    let total_cost: Option<i64> = ended_meetings.iter().map(|(_, cost, _)| *cost).sum();
+    let total_saved: i64 = ended_meetings
+        .iter()
+        .filter_map(|(_, _, saved)| *saved)
+        .sum();
 
     Ok(Section::Meetings.page(
         html! {
             h2 class="text-2xl font-bold mb-2" { "Current Meetings" }
             ul class="mb-4 list-disc pl-8" {
-              @for meeting in current_meetings {
+              @for (meeting, cost, _) in current_meetings {
                 li {
                     (meeting)
+                    @if let Some(cost) = cost {
+                        " - " (Dollars(cost)) " so far"
+                    }
                 }
               }
             }
 
             h2 class="text-2xl font-bold mb-2" { "Ended Meetings" }
+            @if let Some(total_cost) = total_cost {
+              p class="mb-2" {
+                "Estimated cost: " (Dollars(total_cost)) ", saved by ending meetings early: " (Dollars(total_saved))
+              }
+            }
             ul class="mb-4 list-disc pl-8" {
-              @for meeting in ended_meetings {
+              @for (meeting, cost, saved) in ended_meetings {
                 li {
                     (meeting)
+                    @if let Some(cost) = cost {
+                        " - " (Dollars(cost))
+                    }
+                    @if let Some(saved) = saved {
+                        " (saved " (Dollars(saved)) ")"
+                    }
                 }
               }
             }
@@ -231,7 +327,17 @@ async fn meeting(
         (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch meeting").into_response()
     })?;
 
-    let minutes_remaining = if !meeting.is_ended() {
+    let participants = meeting.participants(state.db()).await.map_err(|e| {
+        tracing::error!("Failed to fetch participants: {e:?}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to fetch participants",
+        )
+            .into_response()
+    })?;
+    let headcount = participants.iter().filter(|p| p.is_present()).count();
+
+    let minutes_remaining = if !meeting.is_ended() && !meeting.exempt {
         meeting.fetch_minutes_remaining(&state).await.ok()
     } else {
         None
@@ -261,6 +367,42 @@ async fn meeting(
             "Zoom Meeting ID: " (meeting.zoom_id)
         }
 
+        @if meeting.is_recurring() {
+            p {
+                a href=(format!("/series/{}", meeting.zoom_id)) { "Limits for every meeting in this series" }
+            }
+        }
+
+        @if let Some(deleted_at) = meeting.deleted_at {
+            p {
+                "Deleted in Zoom: " (deleted_at.format("%Y-%m-%d %H:%M:%S"))
+            }
+        }
+
+        @if let Some(scheduled_start_time) = meeting.scheduled_start_time {
+            p {
+                "Scheduled Start Time: " (scheduled_start_time.format("%Y-%m-%d %H:%M:%S"))
+            }
+        }
+
+        @if let Some(scheduled_duration_minutes) = meeting.scheduled_duration_minutes {
+            p {
+                "Scheduled Duration: " (scheduled_duration_minutes) " minutes"
+            }
+        }
+
+        @if let Some(timezone) = &meeting.timezone {
+            p {
+                "Timezone: " (timezone)
+            }
+        }
+
+        @if let Some(agenda) = &meeting.agenda {
+            p {
+                "Agenda: " (agenda)
+            }
+        }
+
         p {
             "Start Time: " (meeting.start_time)
         }
@@ -273,13 +415,25 @@ async fn meeting(
             p {
                 "End Time: " (end_time.format("%Y-%m-%d %H:%M:%S"))
             }
+
+            @if let Some(reason) = meeting.ended_reason.as_deref().and_then(EndedReason::from_db) {
+                p {
+                    "Ended by Just Adios because " (reason.description())
+                }
+            }
         } @else {
             p {
                 "Meeting is still running"
             }
         }
 
-        @if !meeting.is_ended() {
+        @if meeting.exempt {
+            p {
+                "Exempt from limits, Just Adios won't end this meeting"
+            }
+        }
+
+        @if !meeting.is_ended() && !meeting.exempt {
             @if let Some(max_meeting_length_minutes) = meeting.max_meeting_length_minutes {
                 p {
                     "Max Meeting Length: " (max_meeting_length_minutes) " minutes"
@@ -308,6 +462,34 @@ async fn meeting(
             }
         }
 
+        @if let Some(cost) = user.cost_cents(meeting.participant_minutes(&participants)) {
+            p {
+                "Estimated Cost: " (Dollars(cost))
+                @if !meeting.is_ended() {
+                    " so far"
+                }
+            }
+
+            @if let Some(saved) = meeting.saved_participant_minutes(&participants).and_then(|minutes| user.cost_cents(minutes)) {
+                p {
+                    "Saved by ending it before the scheduled end: " (Dollars(saved))
+                }
+            }
+        }
+
+        h2 { "Participants" }
+
+        @if !meeting.is_ended() {
+            p { "In the meeting now: " (headcount) }
+        }
+
+        @if participants.is_empty() {
+            p { "No participants recorded" }
+        } @else {
+            (AttendanceTimeline::new(&meeting, &participants))
+            (AttendanceTable::new(&meeting, &participants))
+        }
+
         a href="/meetings" { "Back to Meetings" }
     }, Some(user)))
 }
@@ -333,13 +515,14 @@ async fn edit_meeting(
             .into_response()
     })?;
 
-    sqlx::query!(
-        "UPDATE meetings SET max_meeting_length_minutes = $1 WHERE meeting_id = $2 AND user_id = $3",
+    let meeting = sqlx::query_as!(
+        DBMeeting,
+        "UPDATE meetings SET max_meeting_length_minutes = $1 WHERE meeting_id = $2 AND user_id = $3 RETURNING *",
         params.max_meeting_length_minutes,
         meeting_id,
         session.user_id,
     )
-    .execute(state.db())
+    .fetch_one(state.db())
     .await
     .map_err(|e| {
         tracing::error!("Failed to update meeting: {e:?}");
@@ -350,6 +533,29 @@ async fn edit_meeting(
             .into_response()
     })?;
 
+    let user = sqlx::query_as!(
+        DBUser,
+        "SELECT * FROM users WHERE user_id = $1",
+        session.user_id,
+    )
+    .fetch_one(state.db())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch user: {e:?}");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch user").into_response()
+    })?;
+
+    schedule_end_meeting(&state, &meeting, &user)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to reschedule meeting end: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to reschedule meeting end",
+            )
+                .into_response()
+        })?;
+
     Ok(Redirect::to(&format!("/meetings/{}", meeting_id)).into_response())
 }
 
@@ -403,16 +609,9 @@ async fn zoom_oauth(
     cookies: Cookies,
 ) -> Result<Response, Response> {
     let zoom_redirect_uri = state.zoom_redirect_url();
-    let client = reqwest::Client::new();
-    let access_token_response = client
-        .post("https://zoom.us/oauth/token")
-        .form(&[
-            ("grant_type", "authorization_code"),
-            ("code", &params.code),
-            ("redirect_uri", &zoom_redirect_uri),
-        ])
-        .basic_auth(&state.zoom.client_id, Some(&state.zoom.client_secret))
-        .send()
+    let token_response = state
+        .zoom_client
+        .exchange_code(&state.zoom, &params.code, &zoom_redirect_uri)
         .await
         .map_err(|e| {
             tracing::error!("Failed to get access token: {e:?}");
@@ -423,65 +622,44 @@ async fn zoom_oauth(
                 .into_response()
         })?;
 
-    let token_response_text = access_token_response.text().await.map_err(|e| {
-        tracing::error!("Failed to get access token: {e:?}");
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to get access token",
-        )
-            .into_response()
-    })?;
-
-    let token_response: ZoomTokenResponse =
-        serde_json::from_str(&token_response_text).map_err(|e| {
-            tracing::error!("Failed to parse access token response: {e:?}");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to parse access token response",
-            )
-                .into_response()
-        })?;
-
-    let user_response = client
-        .get("https://api.zoom.us/v2/users/me")
-        .bearer_auth(&token_response.access_token)
-        .send()
+    let user_info = state
+        .zoom_client
+        .get_current_user(&AccessToken::unowned(token_response.access_token.clone()))
         .await
         .map_err(|e| {
             tracing::error!("Failed to get user info: {e:?}");
             (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get user info").into_response()
         })?;
 
-    tracing::info!("User response Status: {:?}", user_response.status());
-
-    let user_info_text = user_response.text().await.map_err(|e| {
-        tracing::error!("Failed to get user info: {e:?}");
-        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get user info").into_response()
-    })?;
-
-    tracing::info!("User info text: {:?}", user_info_text);
-
-    let user_info: ZoomUser = serde_json::from_str(&user_info_text).map_err(|e| {
-        tracing::error!("Failed to parse user info: {e:?}");
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to parse user info",
-        )
-            .into_response()
-    })?;
-
     tracing::info!("Zoom User info: {user_info:?}");
 
+    let sealed = state
+        .token_cipher
+        .seal(&token_response.access_token, &token_response.refresh_token)
+        .map_err(|e| {
+            tracing::error!("Failed to encrypt tokens: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to encrypt tokens",
+            )
+                .into_response()
+        })?;
+
     let expires_at = Utc::now() + chrono::Duration::seconds(token_response.expires_in);
     let user = sqlx::query_as!(
       DBUser,
-      "INSERT INTO users (zoom_id, display_name, access_token, refresh_token, expires_at, zoom_pic_url) VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (zoom_id) DO UPDATE SET (display_name, access_token, refresh_token, expires_at, zoom_pic_url, updated_at) = ($2, $3, $4, $5, $6, now()) RETURNING *",
+      "INSERT INTO users (zoom_id, display_name, access_token, refresh_token, token_key_id, token_data_key, expires_at, zoom_pic_url, email, timezone, plan_type) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) ON CONFLICT (zoom_id) DO UPDATE SET (display_name, access_token, refresh_token, token_key_id, token_data_key, expires_at, zoom_pic_url, email, timezone, plan_type, updated_at) = ($2, $3, $4, $5, $6, $7, $8, $9, $10, $11, now()) RETURNING *",
       user_info.id,
       user_info.display_name,
-      token_response.access_token,
-      token_response.refresh_token,
+      sealed.access_token,
+      sealed.refresh_token,
+      sealed.key_id,
+      sealed.data_key,
       expires_at,
       user_info.pic_url,
+      user_info.email,
+      user_info.timezone,
+      user_info.plan_type,
     ).fetch_one(state.db()).await.map_err(|e| {
       tracing::error!("Failed to insert user into database: {e:?}");
       (
@@ -507,13 +685,6 @@ async fn zoom_oauth(
     Ok(Redirect::temporary("/").into_response())
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct ZoomUser {
-    id: String,
-    display_name: String,
-    pic_url: Option<String>,
-}
-
 async fn settings(
     State(state): State<AppState>,
     session: DBSession,
@@ -538,6 +709,18 @@ async fn settings(
                 "You are logged in as " (user.display_name)
             }
 
+            @if let Some(email) = &user.email {
+              p { "Email: " (email) }
+            }
+
+            @if let Some(timezone) = &user.timezone {
+              p { "Timezone: " (timezone) }
+            }
+
+            @if let Some(plan_name) = user.plan_name() {
+              p { "Zoom plan: " (plan_name) }
+            }
+
             @if let Some(default_meeting_length_minutes) = user.default_meeting_length_minutes {
               p {
                 "Default meeting length: " (default_meeting_length_minutes) " minutes"
@@ -548,6 +731,26 @@ async fn settings(
               }
             }
 
+            @if let Some(end_when_alone_after_minutes) = user.end_when_alone_after_minutes {
+              p {
+                "End meetings after " (end_when_alone_after_minutes) " minutes with only you (or nobody) in them"
+              }
+            } @else {
+              p {
+                "Meetings are not ended when everyone else leaves"
+              }
+            }
+
+            @if let Some(hourly_rate_cents) = user.hourly_rate_cents {
+              p {
+                "Hourly rate per attendee: " (Dollars(hourly_rate_cents.into()))
+              }
+            } @else {
+              p {
+                "No hourly rate set, meeting costs won't be estimated"
+              }
+            }
+
             a href="/settings/edit" { "Edit Settings" }
         },
         Some(user),
@@ -581,6 +784,12 @@ async fn edit_settings(
             label for="default_meeting_length_minutes" { "Default Meeting Length (minutes)" }
             input type="number" name="default_meeting_length_minutes" value=[user.default_meeting_length_minutes] {}
 
+            label for="end_when_alone_after_minutes" { "End Meetings When Alone For (minutes)" }
+            input type="number" min="1" name="end_when_alone_after_minutes" value=[user.end_when_alone_after_minutes] {}
+
+            label for="hourly_rate" { "Hourly Rate per Attendee ($)" }
+            input type="number" step="0.01" min="0" max=(MAX_HOURLY_RATE_DOLLARS) name="hourly_rate" value=[user.hourly_rate_cents.map(|cents| format!("{}.{:02}", cents / 100, cents % 100))] {}
+
             input type="submit" value="Update" { }
         }
     }, Some(user)))
@@ -591,18 +800,51 @@ async fn update_settings(
     session: DBSession,
     Form(params): Form<EditSettingsParams>,
 ) -> Result<impl IntoResponse, Response> {
-    sqlx::query!(
-        "UPDATE users SET default_meeting_length_minutes = $1 WHERE user_id = $2",
+    let user = sqlx::query_as!(
+        DBUser,
+        "UPDATE users SET default_meeting_length_minutes = $1, end_when_alone_after_minutes = $2, hourly_rate_cents = $3 WHERE user_id = $4 RETURNING *",
         params.default_meeting_length_minutes,
+        params.end_when_alone_after_minutes,
+        params.hourly_rate,
         session.user_id,
     )
-    .execute(state.db())
+    .fetch_one(state.db())
     .await
     .map_err(|e| {
         tracing::error!("Failed to update user: {e:?}");
         (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update user").into_response()
     })?;
 
+    let open_meetings = sqlx::query_as!(
+        DBMeeting,
+        "SELECT * FROM meetings WHERE user_id = $1 AND end_time IS NULL",
+        session.user_id,
+    )
+    .fetch_all(state.db())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch meetings: {e:?}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to fetch meetings",
+        )
+            .into_response()
+    })?;
+
+    // The defaults feed into every open meeting's deadline
+    for meeting in open_meetings {
+        schedule_end_meeting(&state, &meeting, &user)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to reschedule meeting end: {e:?}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to reschedule meeting end",
+                )
+                    .into_response()
+            })?;
+    }
+
     Ok(Redirect::to("/settings").into_response())
 }
 
@@ -610,6 +852,12 @@ async fn update_settings(
 struct EditSettingsParams {
     #[serde(deserialize_with = "empty_string_is_none")]
     default_meeting_length_minutes: Option<i32>,
+    /// Anything under a minute would end meetings the moment the host is alone
+    #[serde(deserialize_with = "empty_string_is_none_positive")]
+    end_when_alone_after_minutes: Option<i32>,
+    /// Dollars in the form, stored as cents
+    #[serde(deserialize_with = "empty_string_is_none_cents")]
+    hourly_rate: Option<i32>,
 }
 
 fn empty_string_is_none<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
@@ -626,3 +874,42 @@ where
         Ok(Some(parsed))
     }
 }
+
+fn empty_string_is_none_positive<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let parsed = empty_string_is_none(deserializer)?;
+    if parsed.is_some_and(|minutes| minutes < 1) {
+        return Err(serde::de::Error::custom("Must be at least 1 minute"));
+    }
+    Ok(parsed)
+}
+
+/// Far more than anyone's time costs, and small enough that cost figures can't overflow
+const MAX_HOURLY_RATE_DOLLARS: f64 = 100_000.0;
+
+fn empty_string_is_none_cents<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    if s.is_empty() {
+        Ok(None)
+    } else {
+        let dollars = s
+            .parse::<f64>()
+            .map_err(|e| serde::de::Error::custom(e.to_string()))?;
+        if !dollars.is_finite() || dollars < 0.0 {
+            return Err(serde::de::Error::custom(
+                "Hourly rate must be a positive amount",
+            ));
+        }
+        if dollars > MAX_HOURLY_RATE_DOLLARS {
+            return Err(serde::de::Error::custom(format!(
+                "Hourly rate can be at most ${MAX_HOURLY_RATE_DOLLARS}"
+            )));
+        }
+        Ok(Some((dollars * 100.0).round() as i32))
+    }
+}
@@ -0,0 +1,122 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Redirect, Response},
+};
+use cja::{app_state::AppState as _, server::session::DBSession, uuid::Uuid};
+use maud::html;
+use reqwest::StatusCode;
+
+use crate::{db::DBUser, views::Section, AppState};
+
+use super::webhooks;
+
+/// How many webhook events the admin page lists
+const RECENT_WEBHOOK_EVENTS_LIMIT: i64 = 100;
+
+async fn fetch_admin(state: &AppState, session: &DBSession) -> Option<DBUser> {
+    let user = sqlx::query_as!(
+        DBUser,
+        "SELECT * FROM users WHERE user_id = $1",
+        session.user_id,
+    )
+    .fetch_one(state.db())
+    .await
+    .map_err(|e| tracing::error!("Failed to fetch user: {e:?}"))
+    .ok()?;
+
+    user.is_admin.then_some(user)
+}
+
+pub(super) async fn webhook_events(
+    State(state): State<AppState>,
+    session: DBSession,
+) -> Result<impl IntoResponse, Response> {
+    let Some(user) = fetch_admin(&state, &session).await else {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let events = webhooks::events::recent(&state, RECENT_WEBHOOK_EVENTS_LIMIT)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch webhook events: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch webhook events",
+            )
+                .into_response()
+        })?;
+
+    Ok(Section::Dashboard.page(
+        html! {
+            h1 { "Webhook Events" }
+
+            table {
+                thead {
+                    tr {
+                        th { "Received" }
+                        th { "Event" }
+                        th { "Event Time" }
+                        th { "Outcome" }
+                        th { "Attempts" }
+                        th { "Processed" }
+                        th { "Body" }
+                        th {}
+                    }
+                }
+                tbody {
+                    @for event in events {
+                        tr {
+                            td { (event.created_at.format("%Y-%m-%d %H:%M:%S")) }
+                            td { (event.event) }
+                            td {
+                                @if let Some(event_ts) = event.event_ts {
+                                    (event_ts.format("%Y-%m-%d %H:%M:%S"))
+                                }
+                            }
+                            td {
+                                (event.outcome.as_deref().unwrap_or("pending"))
+                                @if let Some(error) = &event.error {
+                                    " - " (error)
+                                }
+                            }
+                            td { (event.attempts) }
+                            td {
+                                @if let Some(processed_at) = event.processed_at {
+                                    (processed_at.format("%Y-%m-%d %H:%M:%S"))
+                                }
+                            }
+                            td {
+                                details {
+                                    summary { "Show" }
+                                    pre { (format!("{:#}", event.body)) }
+                                }
+                            }
+                            td {
+                                form action=(format!("/admin/webhook_events/{}/replay", event.webhook_event_id)) method="post" {
+                                    input type="submit" value="Replay" {}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        Some(user),
+    ))
+}
+
+pub(super) async fn replay_webhook_event(
+    State(state): State<AppState>,
+    session: DBSession,
+    Path(webhook_event_id): Path<Uuid>,
+) -> Response {
+    if fetch_admin(&state, &session).await.is_none() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    if let Err(e) = webhooks::replay_event(&state, webhook_event_id).await {
+        tracing::error!("Failed to replay webhook event: {e:?}");
+    }
+
+    Redirect::to("/admin/webhook_events").into_response()
+}
@@ -0,0 +1,273 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use cja::{app_state::AppState as _, server::session::DBSession};
+use maud::html;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    db::{DBMeetingSeries, DBUser, MeetingPolicy},
+    views::Section,
+    zoom::{Meeting, ZoomError},
+    AppState,
+};
+
+use super::empty_string_is_none;
+
+pub(super) async fn fetch_user(state: &AppState, session: &DBSession) -> Result<DBUser, Response> {
+    sqlx::query_as!(
+        DBUser,
+        "SELECT * FROM users WHERE user_id = $1",
+        session.user_id,
+    )
+    .fetch_one(state.db())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch user: {e:?}");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch user").into_response()
+    })
+}
+
+/// Zoom is the source of truth for which occurrences a series has, and only
+/// knows about meetings the user can see
+pub(super) async fn fetch_zoom_meeting(
+    state: &AppState,
+    user: &DBUser,
+    zoom_id: &str,
+) -> Result<Meeting, StatusCode> {
+    user.zoom(state)
+        .get_meeting_details(zoom_id)
+        .await
+        .map_err(|e| match e.downcast_ref() {
+            Some(ZoomError::MeetingNotFound { .. }) => StatusCode::NOT_FOUND,
+            _ => {
+                tracing::error!("Failed to fetch meeting details: {e:?}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })
+}
+
+async fn find_or_create_series(
+    state: &AppState,
+    user: &DBUser,
+    zoom_id: &str,
+) -> cja::Result<DBMeetingSeries> {
+    match DBMeetingSeries::find(state.db(), user.user_id, zoom_id).await? {
+        Some(series) => Ok(series),
+        None => {
+            DBMeetingSeries::upsert(state.db(), user.user_id, zoom_id, MeetingPolicy::default())
+                .await
+        }
+    }
+}
+
+pub(super) async fn series(
+    State(state): State<AppState>,
+    session: DBSession,
+    Path(zoom_id): Path<String>,
+) -> Result<impl IntoResponse, Response> {
+    let user = fetch_user(&state, &session).await?;
+
+    let zoom_meeting = fetch_zoom_meeting(&state, &user, &zoom_id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let series = DBMeetingSeries::find(state.db(), user.user_id, &zoom_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch meeting series: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch meeting series",
+            )
+                .into_response()
+        })?;
+
+    let overrides = match &series {
+        Some(series) => series.occurrences(state.db()).await.map_err(|e| {
+            tracing::error!("Failed to fetch meeting occurrences: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch meeting occurrences",
+            )
+                .into_response()
+        })?,
+        None => vec![],
+    };
+
+    let policy = series.map(|series| series.policy()).unwrap_or_default();
+    let series_limit = policy.max_meeting_length_minutes;
+    let name = zoom_meeting
+        .topic
+        .clone()
+        .unwrap_or_else(|| format!("#{zoom_id}"));
+    let occurrences = zoom_meeting.occurrences.unwrap_or_default();
+
+    Ok(Section::Meetings.page(
+        html! {
+            h1 { "Series - " (name) }
+
+            p {
+                "Zoom Meeting ID: " (zoom_id)
+            }
+
+            p {
+                "These limits apply to every meeting in the series when it starts. Meetings that are already running keep their own limit."
+            }
+
+            form action=(format!("/series/{zoom_id}")) method="post" {
+                label for="max_meeting_length_minutes" { "Max Meeting Length (minutes)" }
+                input type="number" name="max_meeting_length_minutes" value=[series_limit] {}
+
+                label for="exempt" { "Never end these meetings" }
+                input type="checkbox" name="exempt" value="true" checked[policy.exempt] {}
+
+                input type="submit" value="Update" { }
+            }
+
+            h2 { "Upcoming Occurrences" }
+
+            @if occurrences.is_empty() {
+                p { "Zoom has no upcoming occurrences for this meeting" }
+            } @else {
+                table {
+                    thead {
+                        tr {
+                            th { "Start Time" }
+                            th { "Scheduled Duration" }
+                            th { "Max Meeting Length (minutes)" }
+                        }
+                    }
+                    tbody {
+                        @for occurrence in &occurrences {
+                            @let occurrence_limit = overrides
+                                .iter()
+                                .find(|o| o.occurrence_id == occurrence.occurrence_id)
+                                .map(|o| o.max_meeting_length_minutes);
+
+                            tr {
+                                td { (occurrence.start_time.format("%Y-%m-%d %H:%M:%S")) }
+                                td { (occurrence.duration) " minutes" }
+                                td {
+                                    form action=(format!("/series/{zoom_id}/occurrences/{}", occurrence.occurrence_id)) method="post" {
+                                        input type="number" name="max_meeting_length_minutes" value=[occurrence_limit] placeholder=[series_limit] {}
+
+                                        input type="submit" value="Update" { }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            a href="/meetings" { "Back to Meetings" }
+        },
+        Some(user),
+    ))
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub(super) struct EditSeriesParams {
+    #[serde(deserialize_with = "empty_string_is_none")]
+    max_meeting_length_minutes: Option<i32>,
+    /// Unchecked checkboxes aren't submitted at all
+    #[serde(default)]
+    exempt: bool,
+}
+
+impl EditSeriesParams {
+    pub(super) fn policy(&self) -> MeetingPolicy {
+        MeetingPolicy {
+            max_meeting_length_minutes: self.max_meeting_length_minutes,
+            exempt: self.exempt,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub(super) struct EditOccurrenceParams {
+    #[serde(deserialize_with = "empty_string_is_none")]
+    max_meeting_length_minutes: Option<i32>,
+}
+
+pub(super) async fn edit_series(
+    State(state): State<AppState>,
+    session: DBSession,
+    Path(zoom_id): Path<String>,
+    Form(params): Form<EditSeriesParams>,
+) -> Result<impl IntoResponse, Response> {
+    let user = fetch_user(&state, &session).await?;
+
+    fetch_zoom_meeting(&state, &user, &zoom_id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    DBMeetingSeries::upsert(state.db(), user.user_id, &zoom_id, params.policy())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update meeting series: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update meeting series",
+            )
+                .into_response()
+        })?;
+
+    Ok(Redirect::to(&format!("/series/{zoom_id}")).into_response())
+}
+
+pub(super) async fn edit_occurrence(
+    State(state): State<AppState>,
+    session: DBSession,
+    Path((zoom_id, occurrence_id)): Path<(String, String)>,
+    Form(params): Form<EditOccurrenceParams>,
+) -> Result<impl IntoResponse, Response> {
+    let user = fetch_user(&state, &session).await?;
+
+    let zoom_meeting = fetch_zoom_meeting(&state, &user, &zoom_id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let Some(occurrence) = zoom_meeting
+        .occurrences
+        .unwrap_or_default()
+        .into_iter()
+        .find(|occurrence| occurrence.occurrence_id == occurrence_id)
+    else {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let series = find_or_create_series(&state, &user, &zoom_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch meeting series: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch meeting series",
+            )
+                .into_response()
+        })?;
+
+    series
+        .set_occurrence_limit(
+            state.db(),
+            &occurrence.occurrence_id,
+            occurrence.start_time,
+            params.max_meeting_length_minutes,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update meeting occurrence: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update meeting occurrence",
+            )
+                .into_response()
+        })?;
+
+    Ok(Redirect::to(&format!("/series/{zoom_id}")).into_response())
+}
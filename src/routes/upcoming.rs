@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use cja::{app_state::AppState as _, server::session::DBSession};
+use futures::TryStreamExt as _;
+use maud::html;
+use reqwest::StatusCode;
+
+use crate::{db::DBMeetingSeries, views::Section, zoom::MeetingType, AppState};
+
+use super::series::{fetch_user, fetch_zoom_meeting, EditSeriesParams};
+
+/// Scheduled meetings from Zoom, with the limits that will apply once they start
+pub(super) async fn upcoming(
+    State(state): State<AppState>,
+    session: DBSession,
+) -> Result<impl IntoResponse, Response> {
+    let user = fetch_user(&state, &session).await?;
+
+    let meetings: Vec<_> = user
+        .zoom(&state)
+        .get_meetings(MeetingType::Scheduled)
+        .try_collect()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get meetings: {e:?}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get meetings").into_response()
+        })?;
+
+    let policies: HashMap<_, _> = DBMeetingSeries::for_user(state.db(), user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch meeting series: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch meeting series",
+            )
+                .into_response()
+        })?
+        .into_iter()
+        .map(|series| (series.zoom_id.clone(), series.policy()))
+        .collect();
+
+    Ok(Section::Upcoming.page(
+        html! {
+            h1 { "Upcoming Meetings" }
+
+            p {
+                "Limits set here take effect as soon as the meeting starts. Leave the max length empty to use your default."
+            }
+
+            @if meetings.is_empty() {
+                p { "No scheduled meetings in Zoom" }
+            } @else {
+                table {
+                    thead {
+                        tr {
+                            th { "Meeting" }
+                            th { "Start Time" }
+                            th { "Scheduled Duration" }
+                            th { "Limits" }
+                        }
+                    }
+                    tbody {
+                        @for meeting in &meetings {
+                            @let zoom_id = meeting.id.to_string();
+                            @let policy = policies.get(&zoom_id).copied().unwrap_or_default();
+
+                            tr {
+                                td {
+                                    (meeting.topic.as_deref().unwrap_or(&zoom_id))
+                                    @if meeting.is_recurring() {
+                                        " "
+                                        a href=(format!("/series/{zoom_id}")) { "(recurring)" }
+                                    }
+                                }
+                                td {
+                                    @if let Some(start_time) = &meeting.start_time {
+                                        (start_time)
+                                        @if let Some(timezone) = &meeting.timezone {
+                                            " (" (timezone) ")"
+                                        }
+                                    }
+                                }
+                                td {
+                                    @if let Some(duration) = meeting.duration {
+                                        (duration) " minutes"
+                                    }
+                                }
+                                td {
+                                    form action=(format!("/upcoming/{zoom_id}")) method="post" {
+                                        label for="max_meeting_length_minutes" { "Max Meeting Length (minutes)" }
+                                        input type="number" name="max_meeting_length_minutes" value=[policy.max_meeting_length_minutes] {}
+
+                                        label for="exempt" { "Never end" }
+                                        input type="checkbox" name="exempt" value="true" checked[policy.exempt] {}
+
+                                        input type="submit" value="Update" { }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        Some(user),
+    ))
+}
+
+pub(super) async fn edit_upcoming(
+    State(state): State<AppState>,
+    session: DBSession,
+    Path(zoom_id): Path<String>,
+    Form(params): Form<EditSeriesParams>,
+) -> Result<impl IntoResponse, Response> {
+    let user = fetch_user(&state, &session).await?;
+
+    fetch_zoom_meeting(&state, &user, &zoom_id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    DBMeetingSeries::upsert(state.db(), user.user_id, &zoom_id, params.policy())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update meeting limits: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update meeting limits",
+            )
+                .into_response()
+        })?;
+
+    Ok(Redirect::to("/upcoming").into_response())
+}
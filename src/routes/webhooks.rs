@@ -1,21 +1,38 @@
 use axum::{
     extract::State,
-    http::HeaderMap,
+    http::StatusCode,
     response::{IntoResponse, Response},
+    Json,
 };
+use cja::uuid::Uuid;
 use eyre::eyre;
 use hmac::{KeyInit as _, Mac, SimpleHmac};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sha2::Sha256;
 
+use cja::jobs::Job as _;
+
 use crate::{
-    db::{DBMeeting, DBUser},
+    db::{DBMeeting, DBMeetingSeries, DBUser, NewMeeting, ZoomProfileUpdate},
+    jobs::{
+        end_meeting::schedule_end_meeting, enqueue_with, enrich_meeting::EnrichMeeting,
+        process_zoom_webhook::ProcessZoomWebhookJob,
+    },
     AppState,
 };
 
+mod deauthorized;
+pub(crate) mod events;
+mod verify;
+
+use deauthorized::AppDeauthorizedPayload;
+use verify::VerifiedZoomWebhook;
+
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub(crate) struct ZoomWebhookBody {
     event: String,
+    /// Milliseconds since the epoch
+    event_ts: Option<i64>,
     payload: serde_json::Value,
 }
 
@@ -24,6 +41,10 @@ enum ZoomWebhookEvent {
     MeetingEnded(MeetingEndedPayload),
     ParticipantJoined(ParticipantJoinedPayload),
     ParticipantLeft(ParticipantLeftPayload),
+    MeetingUpdated(MeetingUpdatedPayload),
+    MeetingDeleted(MeetingDeletedPayload),
+    UserUpdated(UserUpdatedPayload),
+    AppDeauthorized(AppDeauthorizedPayload),
 }
 
 impl TryFrom<ZoomWebhookBody> for ZoomWebhookEvent {
@@ -41,22 +62,36 @@ impl TryFrom<ZoomWebhookBody> for ZoomWebhookEvent {
             "meeting.participant_left" => {
                 Ok(serde_json::from_value(body.payload).map(Self::ParticipantLeft)?)
             }
+            "meeting.updated" => {
+                Ok(serde_json::from_value(body.payload).map(Self::MeetingUpdated)?)
+            }
+            "meeting.deleted" => {
+                Ok(serde_json::from_value(body.payload).map(Self::MeetingDeleted)?)
+            }
+            "user.updated" => Ok(serde_json::from_value(body.payload).map(Self::UserUpdated)?),
+            "app_deauthorized" => {
+                AppDeauthorizedPayload::from_value(body.payload).map(Self::AppDeauthorized)
+            }
             _ => Err(eyre!("Unknown event type")),
         }
     }
 }
 
 pub(crate) trait ProcessZoomWebhook {
-    async fn process(self, state: &AppState) -> Result<(), Response>;
+    async fn process(self, state: &AppState) -> cja::Result<()>;
 }
 
 impl ProcessZoomWebhook for ZoomWebhookEvent {
-    async fn process(self, state: &AppState) -> Result<(), Response> {
+    async fn process(self, state: &AppState) -> cja::Result<()> {
         match self {
             Self::MeetingStarted(payload) => payload.process(state).await,
             Self::MeetingEnded(payload) => payload.process(state).await,
             Self::ParticipantJoined(payload) => payload.process(state).await,
             Self::ParticipantLeft(payload) => payload.process(state).await,
+            Self::MeetingUpdated(payload) => payload.process(state).await,
+            Self::MeetingDeleted(payload) => payload.process(state).await,
+            Self::UserUpdated(payload) => payload.process(state).await,
+            Self::AppDeauthorized(payload) => payload.process(state).await,
         }
     }
 }
@@ -68,37 +103,38 @@ struct MeetingStartedPayload {
 }
 
 impl ProcessZoomWebhook for MeetingStartedPayload {
-    async fn process(self, state: &AppState) -> Result<(), Response> {
+    async fn process(self, state: &AppState) -> cja::Result<()> {
         let user = sqlx::query_as!(
             DBUser,
             "SELECT * FROM users WHERE zoom_id = $1",
             self.object.host_id
         )
-        .fetch_one(&state.db)
-        .await
-        .map_err(|_| (axum::http::StatusCode::BAD_REQUEST, "User not found").into_response())?;
-
-        let meeting = sqlx::query_as!(
-            DBMeeting,
-            "INSERT INTO meetings (user_id, zoom_id, zoom_uuid, start_time, topic) VALUES ($1, $2, $3, $4, $5) RETURNING *",
-            user.user_id,
-            self.object.id,
-            self.object.uuid,
-            self.object.start_time,
-            self.object.topic
-        ) 
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| {
-            (
-                axum::http::StatusCode::BAD_REQUEST,
-                format!("DB Error: {}", e).into_response(),
-            )
-                .into_response()
-        })?;
+        .fetch_optional(&state.db)
+        .await?;
+
+        let Some(user) = user else {
+            tracing::info!("Meeting started for a host that isn't a user, ignoring");
+            return Ok(());
+        };
+
+        let meeting = NewMeeting {
+            user_id: user.user_id,
+            zoom_id: &self.object.id,
+            zoom_uuid: &self.object.uuid,
+            start_time: self.object.start_time,
+            topic: Some(&self.object.topic),
+            scheduled_duration_minutes: i32::try_from(self.object.duration).ok(),
+        }
+        .upsert(&state.db)
+        .await?;
 
         tracing::info!("Meeting created: {:?}", meeting);
 
+        schedule_end_meeting(state, &meeting, &user).await?;
+        EnrichMeeting::new(meeting.meeting_id)
+            .enqueue(state.clone(), "Zoom Webhook meeting.started".to_string())
+            .await?;
+
         Ok(())
     }
 }
@@ -122,100 +158,442 @@ struct MeetingEndedPayload {
 }
 
 impl ProcessZoomWebhook for MeetingEndedPayload {
-    async fn process(self, state: &AppState) -> Result<(), Response> {
+    async fn process(self, state: &AppState) -> cja::Result<()> {
         let meeting = sqlx::query_as!(
             DBMeeting,
             "UPDATE meetings SET end_time = $1 WHERE zoom_uuid = $2 RETURNING *",
             self.object.end_time,
             self.object.uuid
         )
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| {
-            (
-                axum::http::StatusCode::BAD_REQUEST,
-                format!("DB Error: {}", e).into_response(),
+        .fetch_optional(&state.db)
+        .await?;
+
+        match meeting {
+            Some(meeting) => tracing::info!("Meeting updated: {:?}", meeting),
+            None => tracing::info!("Ended meeting isn't one we track, ignoring"),
+        }
+
+        Ok(())
+    }
+}
+
+/// Zoom sends meeting ids as strings in some events and as numbers in others
+fn zoom_id<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ZoomId {
+        String(String),
+        Number(i64),
+    }
+
+    Ok(match ZoomId::deserialize(deserializer)? {
+        ZoomId::String(id) => id,
+        ZoomId::Number(id) => id.to_string(),
+    })
+}
+
+/// The user whose meeting an event is about. Zoom reuses meeting ids, so the
+/// `zoom_id` alone doesn't tell us that.
+///
+/// Uses the host when Zoom sends it, and otherwise whoever made the change.
+async fn find_host(
+    state: &AppState,
+    host_id: Option<&str>,
+    operator_id: Option<&str>,
+) -> cja::Result<Option<DBUser>> {
+    let Some(zoom_user_id) = host_id.or(operator_id) else {
+        return Ok(None);
+    };
+
+    Ok(sqlx::query_as!(
+        DBUser,
+        "SELECT * FROM users WHERE zoom_id = $1",
+        zoom_user_id
+    )
+    .fetch_optional(&state.db)
+    .await?)
+}
+
+/// `meeting.updated` only includes the fields that changed
+#[derive(Serialize, Deserialize)]
+struct UpdatedMeetingDetails {
+    #[serde(deserialize_with = "zoom_id")]
+    id: String,
+    host_id: Option<String>,
+    topic: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MeetingUpdatedPayload {
+    account_id: String,
+    operator_id: Option<String>,
+    object: UpdatedMeetingDetails,
+}
+
+impl ProcessZoomWebhook for MeetingUpdatedPayload {
+    async fn process(self, state: &AppState) -> cja::Result<()> {
+        let host = find_host(
+            state,
+            self.object.host_id.as_deref(),
+            self.operator_id.as_deref(),
+        )
+        .await?;
+        let Some(host) = host else {
+            tracing::info!("Meeting updated for a host that isn't a user, ignoring");
+            return Ok(());
+        };
+
+        // Every open row has already started, and a new schedule is for a later
+        // occurrence or the whole series, so only the topic carries over
+        let updated = sqlx::query!(
+            "UPDATE meetings SET topic = COALESCE($3, topic), updated_at = now()
+            WHERE zoom_id = $1 AND user_id = $2 AND end_time IS NULL",
+            self.object.id,
+            host.user_id,
+            self.object.topic
+        )
+        .execute(&state.db)
+        .await?;
+
+        tracing::info!(
+            zoom_id = self.object.id,
+            "Meeting updated in Zoom, {} rows changed",
+            updated.rows_affected()
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeletedMeetingDetails {
+    #[serde(deserialize_with = "zoom_id")]
+    id: String,
+    uuid: Option<String>,
+    host_id: Option<String>,
+    /// Only set when single occurrences of a recurring meeting were deleted
+    #[serde(default)]
+    occurrences: Vec<DeletedOccurrence>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeletedOccurrence {
+    occurrence_id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MeetingDeletedPayload {
+    account_id: String,
+    operator_id: Option<String>,
+    object: DeletedMeetingDetails,
+}
+
+impl ProcessZoomWebhook for MeetingDeletedPayload {
+    async fn process(self, state: &AppState) -> cja::Result<()> {
+        let host = find_host(
+            state,
+            self.object.host_id.as_deref(),
+            self.operator_id.as_deref(),
+        )
+        .await?;
+        let Some(host) = host else {
+            tracing::info!("Meeting deleted for a host that isn't a user, ignoring");
+            return Ok(());
+        };
+
+        // Deleting a single occurrence leaves the rest of the series alone, and
+        // meetings that already happened did happen
+        if self.object.occurrences.is_empty() {
+            let deleted = sqlx::query!(
+                "UPDATE meetings SET deleted_at = now(), updated_at = now()
+                WHERE zoom_id = $1 AND user_id = $2 AND deleted_at IS NULL AND end_time IS NULL",
+                self.object.id,
+                host.user_id
             )
-                .into_response()
-        })?;
+            .execute(&state.db)
+            .await?;
+
+            tracing::info!(
+                zoom_id = self.object.id,
+                zoom_uuid = self.object.uuid,
+                "Meeting deleted in Zoom, {} rows marked",
+                deleted.rows_affected()
+            );
+        }
 
-        tracing::info!("Meeting updated: {:?}", meeting);
+        let policies = if self.object.occurrences.is_empty() {
+            DBMeetingSeries::delete_for_zoom_id(&state.db, host.user_id, &self.object.id).await?
+        } else {
+            let occurrence_ids: Vec<_> = self
+                .object
+                .occurrences
+                .into_iter()
+                .map(|occurrence| occurrence.occurrence_id)
+                .collect();
+
+            DBMeetingSeries::delete_occurrences_for_zoom_id(
+                &state.db,
+                host.user_id,
+                &self.object.id,
+                &occurrence_ids,
+            )
+            .await?
+        };
+        if policies > 0 {
+            tracing::info!(
+                zoom_id = self.object.id,
+                "Removed {policies} limits for the deleted meeting"
+            );
+        }
 
         Ok(())
     }
 }
 
-fn verify_zoom_signature(
-    secret_token: &str,
-    headers: &HeaderMap,
-    body: &str,
-) -> Result<(), Response> {
-    let zoom_timestamp = headers.get("x-zm-request-timestamp").unwrap();
-    let message = format!("v0:{}:{}", zoom_timestamp.to_str().unwrap(), body);
+/// `user.updated` only includes the fields that changed
+#[derive(Deserialize)]
+struct UpdatedUserDetails {
+    id: String,
+    #[serde(flatten)]
+    profile: ZoomProfileUpdate,
+}
+
+#[derive(Deserialize)]
+struct UserUpdatedPayload {
+    object: UpdatedUserDetails,
+}
+
+impl ProcessZoomWebhook for UserUpdatedPayload {
+    async fn process(self, state: &AppState) -> cja::Result<()> {
+        let updated = self.object.profile.save(&state.db, &self.object.id).await?;
+
+        if updated == 0 {
+            tracing::info!("Updated Zoom user isn't one of ours, ignoring");
+        } else {
+            tracing::info!("User profile updated from Zoom");
+        }
+
+        Ok(())
+    }
+}
 
+fn hmac_sha256_hex(secret_token: &str, message: &str) -> String {
     let mut mac = SimpleHmac::<Sha256>::new_from_slice(secret_token.as_bytes())
         .expect("HMAC can take key of any size");
     mac.update(message.as_bytes());
 
     let result = mac.finalize();
     let code_bytes = result.into_bytes().to_vec();
-    let code = hex::encode(code_bytes);
-    let signature = format!("v0={}", code);
+    hex::encode(code_bytes)
+}
 
-    let zoom_signature = headers.get("x-zm-signature").unwrap();
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UrlValidationPayload {
+    plain_token: String,
+}
 
-    if zoom_signature != &signature {
-        return Err((
-            axum::http::StatusCode::BAD_REQUEST,
-            "Invalid zoom webhook signature",
-        )
-            .into_response());
-    }
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UrlValidationResponse {
+    plain_token: String,
+    encrypted_token: String,
+}
 
-    Ok(())
+/// Zoom sends `endpoint.url_validation` when the webhook URL is saved and then
+/// periodically after that. We prove we own the endpoint by sending back the
+/// `plainToken` hashed with our secret token.
+fn url_validation_response(secret_token: &str, payload: serde_json::Value) -> Response {
+    let payload: UrlValidationPayload = match serde_json::from_value(payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                format!("Invalid url validation payload: {}", e),
+            )
+                .into_response()
+        }
+    };
+
+    let encrypted_token = hmac_sha256_hex(secret_token, &payload.plain_token);
+
+    Json(UrlValidationResponse {
+        plain_token: payload.plain_token,
+        encrypted_token,
+    })
+    .into_response()
 }
 
+/// Verifies and stores the webhook, then leaves the actual processing to
+/// [`ProcessZoomWebhookJob`] so Zoom gets its 200 straight away
 #[axum_macros::debug_handler]
 pub(crate) async fn zoom_webhook(
     State(app_state): State<AppState>,
-    headers: HeaderMap,
-    body: String,
-) -> Result<(), Response> {
-    verify_zoom_signature(&app_state.zoom.secret_token, &headers, &body)?;
-
-    let body = serde_json::from_str::<ZoomWebhookBody>(&body).unwrap();
-    tracing::info!("Processing zoom webhook event: {:?}", body.event);
-
-    let event = ZoomWebhookEvent::try_from(body.clone()).map_err(|e| {
-        tracing::error!("Invalid zoom webhook body: {:?}", body);
-        (
-            axum::http::StatusCode::BAD_REQUEST,
-            format!("Invalid zoom webhook body: {}", e),
-        )
-            .into_response()
-    })?;
+    VerifiedZoomWebhook { body, raw_body }: VerifiedZoomWebhook,
+) -> Result<Response, Response> {
+    tracing::info!("Received zoom webhook event: {:?}", body.event);
+
+    if body.event == "endpoint.url_validation" {
+        return Ok(url_validation_response(
+            app_state.zoom.current_secret_token(),
+            body.payload,
+        ));
+    }
+
+    let webhook_event_id = record_and_enqueue(&app_state, &body, &raw_body)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to store zoom webhook: {e:?}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to store zoom webhook",
+            )
+                .into_response()
+        })?;
+
+    if webhook_event_id.is_none() {
+        tracing::info!("Already received this zoom webhook, skipping");
+    }
+
+    Ok(().into_response())
+}
+
+/// Stores the webhook and enqueues the job that processes it in one transaction.
+///
+/// If either fails we return an error and Zoom retries the delivery, so an event
+/// is never stored without a job to process it.
+async fn record_and_enqueue(
+    app_state: &AppState,
+    body: &ZoomWebhookBody,
+    raw_body: &str,
+) -> cja::Result<Option<Uuid>> {
+    let mut tx = app_state.db.begin().await?;
+
+    let Some(webhook_event_id) = events::record(&mut *tx, body, raw_body).await? else {
+        return Ok(None);
+    };
+
+    enqueue_with(
+        ProcessZoomWebhookJob::new(webhook_event_id),
+        &mut *tx,
+        "Zoom Webhook",
+        chrono::Utc::now(),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Some(webhook_event_id))
+}
+
+/// Sends a stored webhook back through [`ProcessZoomWebhookJob`] as if Zoom had
+/// just delivered it
+pub(crate) async fn replay_event(app_state: &AppState, webhook_event_id: Uuid) -> cja::Result<()> {
+    events::reset(app_state, webhook_event_id).await?;
+
+    ProcessZoomWebhookJob::new(webhook_event_id)
+        .enqueue(app_state.clone(), "Zoom Webhook Replay".to_string())
+        .await?;
+
+    Ok(())
+}
 
-    event.process(&app_state).await
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum WebhookOutcome {
+    Processed,
+    /// Events we don't handle
+    Ignored,
+}
+
+impl WebhookOutcome {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Processed => "processed",
+            Self::Ignored => "ignored",
+        }
+    }
+}
+
+pub(crate) async fn process_stored_event(
+    app_state: &AppState,
+    webhook_event_id: Uuid,
+) -> cja::Result<WebhookOutcome> {
+    let stored = events::fetch(app_state, webhook_event_id).await?;
+    if stored.body.get("redacted").is_some() {
+        tracing::info!(%webhook_event_id, "Zoom webhook was redacted, nothing to process");
+        return Ok(WebhookOutcome::Ignored);
+    }
+
+    let body: ZoomWebhookBody = serde_json::from_value(stored.body)?;
+
+    tracing::info!(%webhook_event_id, "Processing zoom webhook event: {:?}", body.event);
+
+    let event = match ZoomWebhookEvent::try_from(body) {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::warn!(%webhook_event_id, "Ignoring zoom webhook event: {e}");
+            return Ok(WebhookOutcome::Ignored);
+        }
+    };
+
+    event.process(app_state).await?;
+
+    Ok(WebhookOutcome::Processed)
+}
+
+/// Zoom sends an empty string instead of leaving optional fields out
+fn empty_string_is_none<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+
+    Ok(s.filter(|s| !s.is_empty()))
+}
+
+/// Who is in the meeting feeds into when the host counts as alone, so the
+/// [`crate::jobs::end_meeting::EndMeeting`] deadline may have moved
+async fn reschedule_for_participants(state: &AppState, meeting: &DBMeeting) -> cja::Result<()> {
+    let owner = sqlx::query_as!(
+        DBUser,
+        "SELECT * FROM users WHERE user_id = $1",
+        meeting.user_id
+    )
+    .fetch_one(&state.db)
+    .await?;
+
+    if owner.end_when_alone_after_minutes.is_none() {
+        return Ok(());
+    }
+
+    schedule_end_meeting(state, meeting, &owner).await
 }
 
 #[derive(Serialize, Deserialize)]
 struct ParticipantJoined {
-    email: String,
-    id: String,
-    join_time: String,
-    participant_user_id: String,
+    #[serde(default, deserialize_with = "empty_string_is_none")]
+    email: Option<String>,
+    /// The participant's Zoom user id, empty for guests
+    #[serde(default, deserialize_with = "empty_string_is_none")]
+    id: Option<String>,
+    join_time: chrono::DateTime<chrono::Utc>,
+    participant_user_id: Option<String>,
     participant_uuid: String,
-    user_id: String,
+    user_id: Option<String>,
     user_name: String,
 }
 
 #[derive(Serialize, Deserialize)]
 struct ParticipantJoinedPayloadInner {
+    #[serde(deserialize_with = "zoom_id")]
     id: String,
     participant: ParticipantJoined,
-    start_time: String,
-    timezone: String,
-    topic: String,
+    start_time: Option<String>,
+    timezone: Option<String>,
+    topic: Option<String>,
     r#type: i64,
     uuid: String,
 }
@@ -227,31 +605,78 @@ struct ParticipantJoinedPayload {
 }
 
 impl ProcessZoomWebhook for ParticipantJoinedPayload {
-    async fn process(self, _state: &AppState) -> Result<(), Response> {
-        tracing::info!("Participant joined -- No-Oping for now");
+    async fn process(self, state: &AppState) -> cja::Result<()> {
+        let Some(meeting) = DBMeeting::find_by_zoom_uuid(&state.db, &self.object.uuid).await?
+        else {
+            tracing::info!("Participant joined a meeting we don't track, ignoring");
+            return Ok(());
+        };
+
+        let participant = self.object.participant;
+
+        // If we processed their leave first it left a placeholder, see
+        // `ParticipantLeftPayload`, which this join now fills in
+        let placeholder = sqlx::query!(
+            "UPDATE meeting_participants SET join_time = $3, updated_at = now()
+            WHERE meeting_participant_id = (
+                SELECT meeting_participant_id FROM meeting_participants
+                WHERE meeting_id = $1 AND participant_uuid = $2 AND join_time = leave_time AND leave_time >= $3
+                ORDER BY leave_time LIMIT 1
+            )",
+            meeting.meeting_id,
+            participant.participant_uuid,
+            participant.join_time
+        )
+        .execute(&state.db)
+        .await?;
+
+        // Retries and replays deliver the same join again
+        if placeholder.rows_affected() == 0 {
+            sqlx::query!(
+                "INSERT INTO meeting_participants (meeting_id, participant_uuid, zoom_user_id, user_name, email, join_time) VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (meeting_id, participant_uuid, join_time) DO NOTHING",
+                meeting.meeting_id,
+                participant.participant_uuid,
+                participant.id,
+                participant.user_name,
+                participant.email,
+                participant.join_time
+            )
+            .execute(&state.db)
+            .await?;
+        }
+
+        tracing::info!(meeting_id = %meeting.meeting_id, "Participant joined");
+
+        reschedule_for_participants(state, &meeting).await?;
+
         Ok(())
     }
 }
 
 #[derive(Serialize, Deserialize)]
 struct ParticipantLeft {
-    email: String,
-    id: String,
-    leave_reason: String,
-    leave_time: String,
-    participant_user_id: String,
+    #[serde(default, deserialize_with = "empty_string_is_none")]
+    email: Option<String>,
+    /// The participant's Zoom user id, empty for guests
+    #[serde(default, deserialize_with = "empty_string_is_none")]
+    id: Option<String>,
+    leave_reason: Option<String>,
+    leave_time: chrono::DateTime<chrono::Utc>,
+    participant_user_id: Option<String>,
     participant_uuid: String,
-    registrant_id: String,
-    user_id: String,
+    registrant_id: Option<String>,
+    user_id: Option<String>,
     user_name: String,
 }
 #[derive(Serialize, Deserialize)]
 struct ParticipantLeftPayloadInner {
+    #[serde(deserialize_with = "zoom_id")]
     id: String,
     participant: ParticipantLeft,
-    start_time: String,
-    timezone: String,
-    topic: String,
+    start_time: Option<String>,
+    timezone: Option<String>,
+    topic: Option<String>,
     r#type: i64,
     uuid: String,
 }
@@ -263,8 +688,60 @@ struct ParticipantLeftPayload {
 }
 
 impl ProcessZoomWebhook for ParticipantLeftPayload {
-    async fn process(self, _state: &AppState) -> Result<(), Response> {
-        tracing::info!("Participant left -- No-Oping for now");
+    async fn process(self, state: &AppState) -> cja::Result<()> {
+        let Some(meeting) = DBMeeting::find_by_zoom_uuid(&state.db, &self.object.uuid).await?
+        else {
+            tracing::info!("Participant left a meeting we don't track, ignoring");
+            return Ok(());
+        };
+
+        let participant = self.object.participant;
+        let updated = sqlx::query!(
+            "UPDATE meeting_participants SET leave_time = $1, leave_reason = $2, updated_at = now()
+            WHERE meeting_participant_id = (
+                SELECT meeting_participant_id FROM meeting_participants
+                WHERE meeting_id = $3 AND participant_uuid = $4 AND leave_time IS NULL AND join_time <= $1
+                ORDER BY join_time DESC LIMIT 1
+            )",
+            participant.leave_time,
+            participant.leave_reason,
+            meeting.meeting_id,
+            participant.participant_uuid
+        )
+        .execute(&state.db)
+        .await?;
+
+        if updated.rows_affected() > 0 {
+            tracing::info!(meeting_id = %meeting.meeting_id, "Participant left");
+        } else {
+            // Webhooks can arrive out of order. Record the leave with the join time
+            // left the same as the leave time, for the join to fill in when it
+            // arrives. Retries of a leave we already recorded are skipped.
+            let inserted = sqlx::query!(
+                "INSERT INTO meeting_participants (meeting_id, participant_uuid, zoom_user_id, user_name, email, join_time, leave_time, leave_reason)
+                SELECT $1, $2, $3, $4, $5, $6, $6, $7
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM meeting_participants WHERE meeting_id = $1 AND participant_uuid = $2 AND leave_time = $6
+                )
+                ON CONFLICT (meeting_id, participant_uuid, join_time) DO NOTHING",
+                meeting.meeting_id,
+                participant.participant_uuid,
+                participant.id,
+                participant.user_name,
+                participant.email,
+                participant.leave_time,
+                participant.leave_reason
+            )
+            .execute(&state.db)
+            .await?;
+
+            if inserted.rows_affected() > 0 {
+                tracing::warn!(meeting_id = %meeting.meeting_id, "Participant left before we saw them join");
+            }
+        }
+
+        reschedule_for_participants(state, &meeting).await?;
+
         Ok(())
     }
 }
@@ -0,0 +1,142 @@
+use cja::{jobs::Job as _, uuid::Uuid};
+use serde::Deserialize;
+
+use crate::{
+    db::DBUser,
+    jobs::{
+        check_live_meetings::CheckLiveUserMeetings, end_meeting::EndMeeting,
+        enrich_meeting::EnrichMeeting, process_zoom_webhook::ProcessZoomWebhookJob,
+        reconcile_meetings::ReconcileUserMeetings, refresh_user_profile::RefreshUserProfile,
+    },
+    AppState,
+};
+
+use super::{events, ProcessZoomWebhook};
+
+/// Sent when a user removes the app from their Zoom account
+#[derive(Deserialize)]
+pub(super) struct AppDeauthorizedPayload {
+    account_id: String,
+    user_id: String,
+    deauthorization_time: chrono::DateTime<chrono::Utc>,
+    /// The whole payload, Zoom wants it echoed back to the data compliance endpoint
+    #[serde(skip)]
+    raw: serde_json::Value,
+}
+
+impl AppDeauthorizedPayload {
+    pub(super) fn from_value(payload: serde_json::Value) -> cja::Result<Self> {
+        let mut parsed: Self = serde_json::from_value(payload.clone())?;
+        parsed.raw = payload;
+
+        Ok(parsed)
+    }
+
+    /// Deletes the user along with their sessions, meetings and any jobs that were
+    /// still waiting to run for them, redacts the webhooks we stored about them,
+    /// and records that we did so
+    async fn purge_user_data(&self, state: &AppState) -> cja::Result<()> {
+        let mut tx = state.db.begin().await?;
+
+        let user = sqlx::query_as!(
+            DBUser,
+            "SELECT * FROM users WHERE zoom_id = $1 FOR UPDATE",
+            self.user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(user) = user {
+            // Job payloads are the serialized id the job works on
+            sqlx::query!(
+                "DELETE FROM jobs WHERE locked_at IS NULL AND (
+                    (name = ANY($1) AND payload #>> '{}' IN (SELECT meeting_id::text FROM meetings WHERE user_id = $3))
+                    OR (name = ANY($2) AND payload #>> '{}' = $3::text)
+                )",
+                &[EndMeeting::NAME, EnrichMeeting::NAME] as &[&str],
+                &[
+                    CheckLiveUserMeetings::NAME,
+                    RefreshUserProfile::NAME,
+                    ReconcileUserMeetings::NAME,
+                ] as &[&str],
+                user.user_id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query!("DELETE FROM sessions WHERE user_id = $1", user.user_id)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query!("DELETE FROM meetings WHERE user_id = $1", user.user_id)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query!("DELETE FROM users WHERE user_id = $1", user.user_id)
+                .execute(&mut *tx)
+                .await?;
+
+            tracing::info!(user_id = %user.user_id, "Deleted data for deauthorized Zoom user");
+        } else {
+            tracing::info!("Deauthorized Zoom user has no data to delete");
+        }
+
+        // Stored webhooks can be about users we never had a row for, like
+        // participants, so this doesn't depend on finding the user
+        let redacted = events::redact_for_zoom_user(&mut *tx, &self.user_id).await?;
+        tracing::info!(
+            "Redacted {} stored webhooks for deauthorized Zoom user",
+            redacted.len()
+        );
+
+        let redacted: Vec<_> = redacted.iter().map(Uuid::to_string).collect();
+        sqlx::query!(
+            "DELETE FROM jobs WHERE locked_at IS NULL AND name = $1 AND payload #>> '{}' = ANY($2)",
+            ProcessZoomWebhookJob::NAME,
+            &redacted
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "INSERT INTO zoom_deauthorizations (zoom_user_id, account_id, deauthorization_time) VALUES ($1, $2, $3)
+            ON CONFLICT (zoom_user_id, deauthorization_time) DO NOTHING",
+            self.user_id,
+            self.account_id,
+            self.deauthorization_time
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn acknowledge(&self, state: &AppState) -> cja::Result<()> {
+        state
+            .zoom_client
+            .acknowledge_data_compliance(&state.zoom, &self.raw)
+            .await?;
+
+        sqlx::query!(
+            "UPDATE zoom_deauthorizations SET compliance_acknowledged_at = now() WHERE zoom_user_id = $1 AND deauthorization_time = $2",
+            self.user_id,
+            self.deauthorization_time
+        )
+        .execute(&state.db)
+        .await?;
+
+        // Only now that Zoom has its acknowledgement can we forget this event too
+        events::redact_deauthorizations(&state.db, &self.user_id).await?;
+
+        Ok(())
+    }
+}
+
+impl ProcessZoomWebhook for AppDeauthorizedPayload {
+    async fn process(self, state: &AppState) -> cja::Result<()> {
+        self.purge_user_data(state).await?;
+        self.acknowledge(state).await
+    }
+}
@@ -0,0 +1,160 @@
+use cja::uuid::Uuid;
+use sha2::{Digest as _, Sha256};
+
+use crate::{db::DBWebhookEvent, AppState};
+
+use super::ZoomWebhookBody;
+
+/// Zoom retries a delivery with the exact same event, `event_ts` and payload, so
+/// hashing those gives us a stable identity for the event
+fn dedupe_key(body: &ZoomWebhookBody) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(body.event.as_bytes());
+    hasher.update(b":");
+    hasher.update(body.event_ts.unwrap_or_default().to_string().as_bytes());
+    hasher.update(b":");
+    hasher.update(body.payload.to_string().as_bytes());
+
+    hex::encode(hasher.finalize())
+}
+
+/// Stores a verified webhook, returning `None` when we have already seen this event
+pub(super) async fn record(
+    executor: impl sqlx::PgExecutor<'_>,
+    body: &ZoomWebhookBody,
+    raw_body: &str,
+) -> cja::Result<Option<Uuid>> {
+    let raw_body: serde_json::Value = serde_json::from_str(raw_body)?;
+    let event_ts = body
+        .event_ts
+        .and_then(chrono::DateTime::<chrono::Utc>::from_timestamp_millis);
+
+    let webhook_event_id = sqlx::query_scalar!(
+        "INSERT INTO webhook_events (event, event_ts, dedupe_key, body) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (dedupe_key) DO NOTHING
+        RETURNING webhook_event_id",
+        body.event,
+        event_ts,
+        dedupe_key(body),
+        raw_body
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(webhook_event_id)
+}
+
+pub(crate) async fn record_outcome(
+    state: &AppState,
+    webhook_event_id: Uuid,
+    outcome: &str,
+    error: Option<String>,
+) -> cja::Result<()> {
+    sqlx::query!(
+        "UPDATE webhook_events SET outcome = $1, error = $2, processed_at = now() WHERE webhook_event_id = $3",
+        outcome,
+        error,
+        webhook_event_id
+    )
+    .execute(&state.db)
+    .await?;
+
+    Ok(())
+}
+
+/// Counts a processing attempt, returning how many there have been so far
+pub(crate) async fn start_attempt(state: &AppState, webhook_event_id: Uuid) -> cja::Result<i32> {
+    Ok(sqlx::query_scalar!(
+        "UPDATE webhook_events SET attempts = attempts + 1 WHERE webhook_event_id = $1 RETURNING attempts",
+        webhook_event_id
+    )
+    .fetch_one(&state.db)
+    .await?)
+}
+
+/// Clears the outcome and attempts so a replay gets a full set of retries
+pub(super) async fn reset(state: &AppState, webhook_event_id: Uuid) -> cja::Result<()> {
+    sqlx::query!(
+        "UPDATE webhook_events SET outcome = NULL, error = NULL, processed_at = NULL, attempts = 0 WHERE webhook_event_id = $1",
+        webhook_event_id
+    )
+    .execute(&state.db)
+    .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn fetch(state: &AppState, webhook_event_id: Uuid) -> cja::Result<DBWebhookEvent> {
+    Ok(sqlx::query_as!(
+        DBWebhookEvent,
+        "SELECT * FROM webhook_events WHERE webhook_event_id = $1",
+        webhook_event_id
+    )
+    .fetch_one(&state.db)
+    .await?)
+}
+
+pub(crate) async fn recent(state: &AppState, limit: i64) -> cja::Result<Vec<DBWebhookEvent>> {
+    Ok(sqlx::query_as!(
+        DBWebhookEvent,
+        "SELECT * FROM webhook_events ORDER BY created_at DESC LIMIT $1",
+        limit
+    )
+    .fetch_all(&state.db)
+    .await?)
+}
+
+/// Replaces the stored body of every event about a Zoom user, as the host, the
+/// participant or the user themselves. The event type, timestamps and outcome are
+/// kept so the admin page still shows what happened.
+///
+/// `app_deauthorized` events are left alone since we still need them to
+/// acknowledge the deauthorization, see [`redact_deauthorizations`].
+///
+/// Returns the ids of the events that were redacted.
+pub(super) async fn redact_for_zoom_user(
+    executor: impl sqlx::PgExecutor<'_>,
+    zoom_user_id: &str,
+) -> cja::Result<Vec<Uuid>> {
+    Ok(sqlx::query_scalar!(
+        "UPDATE webhook_events SET body = jsonb_build_object('event', event, 'redacted', true), error = NULL
+        WHERE NOT body ? 'redacted' AND event != 'app_deauthorized' AND (
+            body->'payload'->'object'->>'host_id' = $1
+            OR body->'payload'->'object'->'participant'->>'id' = $1
+            OR body->'payload'->>'operator_id' = $1
+            OR (event LIKE 'user.%' AND body->'payload'->'object'->>'id' = $1)
+        )
+        RETURNING webhook_event_id",
+        zoom_user_id
+    )
+    .fetch_all(executor)
+    .await?)
+}
+
+pub(super) async fn redact_deauthorizations(
+    executor: impl sqlx::PgExecutor<'_>,
+    zoom_user_id: &str,
+) -> cja::Result<u64> {
+    let redacted = sqlx::query!(
+        "UPDATE webhook_events SET body = jsonb_build_object('event', event, 'redacted', true), error = NULL
+        WHERE NOT body ? 'redacted' AND event = 'app_deauthorized' AND body->'payload'->>'user_id' = $1",
+        zoom_user_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(redacted.rows_affected())
+}
+
+/// Forgets events we are done with once they are older than `retention`. Events
+/// that are still waiting on a retry are kept.
+pub(crate) async fn prune(state: &AppState, retention: chrono::Duration) -> cja::Result<u64> {
+    let pruned = sqlx::query!(
+        "DELETE FROM webhook_events WHERE created_at < $1 AND outcome IS NOT NULL AND outcome != 'retrying'",
+        chrono::Utc::now() - retention
+    )
+    .execute(&state.db)
+    .await?;
+
+    Ok(pruned.rows_affected())
+}
@@ -0,0 +1,261 @@
+use axum::{
+    extract::{FromRequest, Request},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use hmac::{KeyInit as _, Mac, SimpleHmac};
+use sha2::Sha256;
+
+use crate::AppState;
+
+use super::ZoomWebhookBody;
+
+/// How far the `x-zm-request-timestamp` may be from our clock before we treat the
+/// request as a replay
+const MAX_TIMESTAMP_SKEW_SECONDS: i64 = 5 * 60;
+
+/// A webhook body that was signed by Zoom with one of our secret tokens.
+///
+/// Zoom signs `v0:{timestamp}:{body}` with HMAC-SHA256 and sends the result in the
+/// `x-zm-signature` header. Every configured secret token is tried so the token can
+/// be rotated without dropping webhooks.
+#[derive(Debug)]
+pub(crate) struct VerifiedZoomWebhook {
+    pub(crate) body: ZoomWebhookBody,
+    pub(crate) raw_body: String,
+}
+
+#[derive(Debug)]
+pub(crate) enum ZoomWebhookRejection {
+    MissingHeader(&'static str),
+    InvalidTimestamp,
+    StaleTimestamp,
+    InvalidSignature,
+    UnreadableBody,
+    InvalidBody(serde_json::Error),
+}
+
+impl IntoResponse for ZoomWebhookRejection {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Self::MissingHeader(header) => {
+                (StatusCode::UNAUTHORIZED, format!("Missing {header} header"))
+            }
+            Self::InvalidTimestamp => (
+                StatusCode::UNAUTHORIZED,
+                "Invalid zoom webhook timestamp".to_string(),
+            ),
+            Self::StaleTimestamp => (
+                StatusCode::UNAUTHORIZED,
+                "Zoom webhook timestamp is too old".to_string(),
+            ),
+            Self::InvalidSignature => (
+                StatusCode::UNAUTHORIZED,
+                "Invalid zoom webhook signature".to_string(),
+            ),
+            Self::UnreadableBody => (
+                StatusCode::BAD_REQUEST,
+                "Could not read zoom webhook body".to_string(),
+            ),
+            Self::InvalidBody(e) => (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid zoom webhook body: {e}"),
+            ),
+        };
+
+        tracing::warn!("Rejected zoom webhook: {message}");
+
+        (status, message).into_response()
+    }
+}
+
+#[async_trait::async_trait]
+impl FromRequest<AppState> for VerifiedZoomWebhook {
+    type Rejection = ZoomWebhookRejection;
+
+    async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let headers = req.headers().clone();
+        let timestamp = header(&headers, "x-zm-request-timestamp")?;
+        let signature = header(&headers, "x-zm-signature")?;
+
+        let raw_body = String::from_request(req, state)
+            .await
+            .map_err(|_| ZoomWebhookRejection::UnreadableBody)?;
+
+        verify(
+            &state.zoom.secret_tokens,
+            &timestamp,
+            &signature,
+            &raw_body,
+            chrono::Utc::now().timestamp(),
+        )?;
+
+        let body = serde_json::from_str(&raw_body).map_err(ZoomWebhookRejection::InvalidBody)?;
+
+        Ok(Self { body, raw_body })
+    }
+}
+
+/// Rejects replays by their timestamp before spending any time on the signature
+fn verify(
+    secret_tokens: &[String],
+    timestamp: &str,
+    signature: &str,
+    raw_body: &str,
+    now_seconds: i64,
+) -> Result<(), ZoomWebhookRejection> {
+    let timestamp_seconds: i64 = timestamp
+        .parse()
+        .map_err(|_| ZoomWebhookRejection::InvalidTimestamp)?;
+    if (now_seconds - timestamp_seconds).abs() > MAX_TIMESTAMP_SKEW_SECONDS {
+        return Err(ZoomWebhookRejection::StaleTimestamp);
+    }
+
+    let message = format!("v0:{timestamp}:{raw_body}");
+    if !signature_matches(secret_tokens, &message, signature) {
+        return Err(ZoomWebhookRejection::InvalidSignature);
+    }
+
+    Ok(())
+}
+
+fn header(headers: &HeaderMap, name: &'static str) -> Result<String, ZoomWebhookRejection> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .ok_or(ZoomWebhookRejection::MissingHeader(name))
+}
+
+/// Checks the signature against every secret token using a constant time comparison
+fn signature_matches(secret_tokens: &[String], message: &str, signature: &str) -> bool {
+    let Some(signature) = signature
+        .strip_prefix("v0=")
+        .and_then(|hex_signature| hex::decode(hex_signature).ok())
+    else {
+        return false;
+    };
+
+    secret_tokens.iter().any(|secret_token| {
+        let mut mac = SimpleHmac::<Sha256>::new_from_slice(secret_token.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(message.as_bytes());
+
+        mac.verify_slice(&signature).is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_729_425_600;
+    const BODY: &str = r#"{"event":"meeting.started","payload":{}}"#;
+
+    fn tokens() -> Vec<String> {
+        vec!["current-token".to_string(), "rotated-token".to_string()]
+    }
+
+    fn sign(secret_token: &str, timestamp: &str, body: &str) -> String {
+        let mut mac = SimpleHmac::<Sha256>::new_from_slice(secret_token.as_bytes()).unwrap();
+        mac.update(format!("v0:{timestamp}:{body}").as_bytes());
+
+        format!("v0={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn accepts_a_valid_signature() {
+        let timestamp = NOW.to_string();
+        let signature = sign("current-token", &timestamp, BODY);
+
+        assert!(verify(&tokens(), &timestamp, &signature, BODY, NOW).is_ok());
+    }
+
+    #[test]
+    fn accepts_a_signature_from_the_rotated_token() {
+        let timestamp = NOW.to_string();
+        let signature = sign("rotated-token", &timestamp, BODY);
+
+        assert!(verify(&tokens(), &timestamp, &signature, BODY, NOW).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_wrong_signature() {
+        let timestamp = NOW.to_string();
+        let signature = sign("someone-elses-token", &timestamp, BODY);
+
+        assert!(matches!(
+            verify(&tokens(), &timestamp, &signature, BODY, NOW),
+            Err(ZoomWebhookRejection::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn rejects_a_signature_for_a_different_body() {
+        let timestamp = NOW.to_string();
+        let signature = sign("current-token", &timestamp, BODY);
+
+        assert!(matches!(
+            verify(&tokens(), &timestamp, &signature, "{}", NOW),
+            Err(ZoomWebhookRejection::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_signatures() {
+        let timestamp = NOW.to_string();
+        let signature = sign("current-token", &timestamp, BODY);
+        let without_prefix = signature.trim_start_matches("v0=");
+
+        for signature in [without_prefix, "v0=not-hex", "v0=", ""] {
+            assert!(matches!(
+                verify(&tokens(), &timestamp, signature, BODY, NOW),
+                Err(ZoomWebhookRejection::InvalidSignature)
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_timestamps_outside_the_window_even_when_signed() {
+        for timestamp in [
+            NOW - MAX_TIMESTAMP_SKEW_SECONDS - 1,
+            NOW + MAX_TIMESTAMP_SKEW_SECONDS + 1,
+        ] {
+            let timestamp = timestamp.to_string();
+            let signature = sign("current-token", &timestamp, BODY);
+
+            assert!(matches!(
+                verify(&tokens(), &timestamp, &signature, BODY, NOW),
+                Err(ZoomWebhookRejection::StaleTimestamp)
+            ));
+        }
+    }
+
+    #[test]
+    fn checks_the_timestamp_before_the_signature() {
+        let timestamp = (NOW - MAX_TIMESTAMP_SKEW_SECONDS - 1).to_string();
+
+        assert!(matches!(
+            verify(&tokens(), &timestamp, "v0=not-hex", BODY, NOW),
+            Err(ZoomWebhookRejection::StaleTimestamp)
+        ));
+    }
+
+    #[test]
+    fn accepts_timestamps_at_the_edge_of_the_window() {
+        let timestamp = (NOW - MAX_TIMESTAMP_SKEW_SECONDS).to_string();
+        let signature = sign("current-token", &timestamp, BODY);
+
+        assert!(verify(&tokens(), &timestamp, &signature, BODY, NOW).is_ok());
+    }
+
+    #[test]
+    fn rejects_unparseable_timestamps() {
+        let signature = sign("current-token", "yesterday", BODY);
+
+        assert!(matches!(
+            verify(&tokens(), "yesterday", &signature, BODY, NOW),
+            Err(ZoomWebhookRejection::InvalidTimestamp)
+        ));
+    }
+}
@@ -5,6 +5,8 @@ use maud::{html, Markup, Render};
 
 use crate::db::DBUser;
 
+pub mod attendance;
+pub mod cost;
 mod footer;
 mod header;
 
@@ -12,6 +14,7 @@ mod header;
 pub enum Section {
     Dashboard,
     Meetings,
+    Upcoming,
     Settings,
 }
 
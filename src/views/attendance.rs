@@ -0,0 +1,240 @@
+use chrono::{DateTime, Utc};
+use maud::{html, Markup, Render};
+
+use crate::db::{DBMeeting, DBMeetingParticipant};
+
+const TIMELINE_WIDTH: f64 = 600.0;
+const TIMELINE_HEIGHT: f64 = 150.0;
+
+/// A step chart of how many people were in the meeting over time
+pub struct AttendanceTimeline<'a> {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    participants: &'a [DBMeetingParticipant],
+}
+
+impl<'a> AttendanceTimeline<'a> {
+    pub fn new(meeting: &DBMeeting, participants: &'a [DBMeetingParticipant]) -> Self {
+        let start = participants
+            .iter()
+            .map(|p| p.join_time)
+            .fold(meeting.start_time, DateTime::min);
+        let end = meeting.end_time.unwrap_or_else(Utc::now);
+
+        Self {
+            start,
+            end: end.max(start),
+            participants,
+        }
+    }
+
+    /// Every time the headcount changed, along with the new headcount
+    fn headcount_changes(&self) -> Vec<(DateTime<Utc>, i64)> {
+        let mut changes: Vec<(DateTime<Utc>, i64)> = self
+            .participants
+            .iter()
+            // Placeholders for a leave we never saw the join for were never in the meeting
+            .filter(|p| p.leave_time != Some(p.join_time))
+            .flat_map(|p| {
+                std::iter::once((p.join_time, 1)).chain(p.leave_time.map(|leave| (leave, -1)))
+            })
+            .collect();
+        // Leaves before joins at the same instant so the count never overshoots
+        changes.sort();
+
+        let mut headcount = 0;
+        changes
+            .into_iter()
+            .map(|(time, change)| {
+                headcount += change;
+                (time, headcount)
+            })
+            .collect()
+    }
+
+    fn x(&self, time: DateTime<Utc>) -> f64 {
+        let total = (self.end - self.start).num_seconds().max(1) as f64;
+        let elapsed = (time.clamp(self.start, self.end) - self.start).num_seconds() as f64;
+
+        elapsed / total * TIMELINE_WIDTH
+    }
+}
+
+impl Render for AttendanceTimeline<'_> {
+    fn render(&self) -> Markup {
+        let changes = self.headcount_changes();
+        let max_headcount = changes
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let y =
+            |count: i64| TIMELINE_HEIGHT - (count as f64 / max_headcount as f64 * TIMELINE_HEIGHT);
+
+        let mut path = format!("M 0 {:.1}", y(0));
+        for (time, count) in &changes {
+            path.push_str(&format!(" H {:.1} V {:.1}", self.x(*time), y(*count)));
+        }
+        path.push_str(&format!(" H {:.1}", TIMELINE_WIDTH));
+
+        html! {
+            svg
+                class="w-full max-w-2xl border border-gray-200"
+                viewBox=(format!("-40 -10 {} {}", TIMELINE_WIDTH + 50.0, TIMELINE_HEIGHT + 30.0))
+                role="img"
+                aria-label="Participants in the meeting over time"
+            {
+                line x1="0" y1=(TIMELINE_HEIGHT) x2=(TIMELINE_WIDTH) y2=(TIMELINE_HEIGHT) stroke="#9ca3af" {}
+                line x1="0" y1="0" x2="0" y2=(TIMELINE_HEIGHT) stroke="#9ca3af" {}
+
+                text x="-8" y="4" text-anchor="end" font-size="12" { (max_headcount) }
+                text x="-8" y=(TIMELINE_HEIGHT) text-anchor="end" font-size="12" { "0" }
+                text x="0" y=(TIMELINE_HEIGHT + 16.0) font-size="12" { (self.start.format("%H:%M")) }
+                text x=(TIMELINE_WIDTH) y=(TIMELINE_HEIGHT + 16.0) text-anchor="end" font-size="12" { (self.end.format("%H:%M")) }
+
+                path d=(path) fill="none" stroke="#2563eb" stroke-width="2" {}
+            }
+        }
+    }
+}
+
+/// One row per participant, combining every time they joined
+pub struct AttendanceTable<'a> {
+    meeting: &'a DBMeeting,
+    participants: &'a [DBMeetingParticipant],
+}
+
+impl<'a> AttendanceTable<'a> {
+    pub fn new(meeting: &'a DBMeeting, participants: &'a [DBMeetingParticipant]) -> Self {
+        Self {
+            meeting,
+            participants,
+        }
+    }
+
+    /// Joins grouped by participant, in the order they first joined
+    fn attendees(&self) -> Vec<Vec<&'a DBMeetingParticipant>> {
+        let mut attendees: Vec<Vec<&DBMeetingParticipant>> = vec![];
+
+        for participant in self.participants {
+            match attendees
+                .iter_mut()
+                .find(|joins| joins[0].participant_uuid == participant.participant_uuid)
+            {
+                Some(joins) => joins.push(participant),
+                None => attendees.push(vec![participant]),
+            }
+        }
+
+        attendees
+    }
+}
+
+impl Render for AttendanceTable<'_> {
+    fn render(&self) -> Markup {
+        html! {
+            table class="min-w-full divide-y divide-gray-300 text-left" {
+                thead {
+                    tr {
+                        th { "Name" }
+                        th { "Email" }
+                        th { "Joined / Left" }
+                        th { "Attended" }
+                    }
+                }
+                tbody {
+                    @for joins in self.attendees() {
+                        tr {
+                            td { (joins[0].user_name) }
+                            td { (joins[0].email.as_deref().unwrap_or("")) }
+                            td {
+                                ul {
+                                    @for join in &joins {
+                                        li {
+                                            (join.join_time.format("%H:%M:%S")) " - "
+                                            @if let Some(leave_time) = join.leave_time {
+                                                (leave_time.format("%H:%M:%S"))
+                                                @if let Some(leave_reason) = &join.leave_reason {
+                                                    " (" (leave_reason) ")"
+                                                }
+                                            } @else {
+                                                "still here"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            td { (self.meeting.attended_minutes(joins.iter().copied())) " minutes" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cja::uuid::Uuid;
+
+    use super::*;
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_729_425_600 + minute * 60, 0).unwrap()
+    }
+
+    fn participant(join: i64, leave: Option<i64>) -> DBMeetingParticipant {
+        DBMeetingParticipant {
+            meeting_participant_id: Uuid::new_v4(),
+            meeting_id: Uuid::nil(),
+            participant_uuid: Uuid::new_v4().to_string(),
+            zoom_user_id: None,
+            user_name: "Guest".to_string(),
+            email: None,
+            join_time: at(join),
+            leave_time: leave.map(at),
+            leave_reason: None,
+            created_at: at(join),
+            updated_at: at(join),
+        }
+    }
+
+    fn headcounts(participants: &[DBMeetingParticipant]) -> Vec<(i64, i64)> {
+        let timeline = AttendanceTimeline {
+            start: at(0),
+            end: at(60),
+            participants,
+        };
+
+        timeline
+            .headcount_changes()
+            .into_iter()
+            .map(|(time, count)| ((time - at(0)).num_minutes(), count))
+            .collect()
+    }
+
+    #[test]
+    fn counts_joins_and_leaves() {
+        let participants = [participant(0, Some(30)), participant(10, None)];
+
+        assert_eq!(headcounts(&participants), vec![(0, 1), (10, 2), (30, 1)]);
+    }
+
+    #[test]
+    fn leaves_come_before_joins_at_the_same_instant() {
+        let participants = [participant(0, Some(20)), participant(20, Some(40))];
+
+        assert_eq!(
+            headcounts(&participants),
+            vec![(0, 1), (20, 0), (20, 1), (40, 0)]
+        );
+    }
+
+    #[test]
+    fn skips_placeholder_rows() {
+        let participants = [participant(0, Some(30)), participant(15, Some(15))];
+
+        assert_eq!(headcounts(&participants), vec![(0, 1), (30, 0)]);
+    }
+}
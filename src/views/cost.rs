@@ -0,0 +1,22 @@
+use maud::{html, Markup, Render};
+
+/// Renders an amount in cents as dollars, like `$1,234.56`
+pub struct Dollars(pub i64);
+
+impl Render for Dollars {
+    fn render(&self) -> Markup {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        let dollars = (cents / 100).to_string();
+
+        let mut grouped = String::new();
+        for (i, digit) in dollars.chars().enumerate() {
+            if i > 0 && (dollars.len() - i).is_multiple_of(3) {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+
+        html! { (format!("{sign}${grouped}.{:02}", cents % 100)) }
+    }
+}
@@ -29,6 +29,11 @@ impl Header {
                 text: "Meetings",
                 section: Section::Meetings,
             },
+            HeaderLink {
+                href: "/upcoming",
+                text: "Upcoming",
+                section: Section::Upcoming,
+            },
             HeaderLink {
                 href: "/settings",
                 text: "Settings",
@@ -187,6 +192,7 @@ impl Render for Header {
                           @match self.current_section {
                             Section::Dashboard => "Dashboard",
                             Section::Meetings => "Meetings",
+                            Section::Upcoming => "Upcoming",
                             Section::Settings => "Settings",
                           }
                         }
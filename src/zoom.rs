@@ -1,8 +1,164 @@
+use std::{sync::Arc, time::Duration};
+
+use cja::uuid::Uuid;
 use eyre::Context;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{db::ZoomProfileUpdate, routes::ZoomTokenResponse, ZoomState};
+
+mod error;
+mod rate_limit;
+mod user_client;
+
+pub(crate) use error::ZoomError;
+pub(crate) use rate_limit::{RateLimitCategory, RateLimitSnapshot};
+pub(crate) use user_client::UserZoomClient;
+
+const DEFAULT_API_BASE_URL: &str = "https://api.zoom.us";
+const DEFAULT_OAUTH_BASE_URL: &str = "https://zoom.us";
+
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest page size the list meetings endpoint accepts
+const MEETINGS_PAGE_SIZE: &str = "300";
+
+/// Shared HTTP client for the Zoom REST API and OAuth endpoints.
+///
+/// The base URLs can be overridden with `ZOOM_API_BASE_URL` and `ZOOM_OAUTH_BASE_URL`
+/// so the whole app can be pointed at a mock Zoom server.
+#[derive(Clone, Debug)]
+pub(crate) struct ZoomClient {
+    http: Client,
+    api_base_url: String,
+    oauth_base_url: String,
+    rate_limiter: Arc<rate_limit::RateLimiter>,
+}
+
+/// An access token along with the user it belongs to, so requests can be rate
+/// limited per user.
+#[derive(Clone, Debug)]
+pub(crate) struct AccessToken {
+    user_id: Option<Uuid>,
+    token: String,
+}
+
+impl AccessToken {
+    pub(crate) fn for_user(user_id: Uuid, token: impl Into<String>) -> Self {
+        Self {
+            user_id: Some(user_id),
+            token: token.into(),
+        }
+    }
+
+    /// A token we don't have a user for yet, like during the OAuth login
+    pub(crate) fn unowned(token: impl Into<String>) -> Self {
+        Self {
+            user_id: None,
+            token: token.into(),
+        }
+    }
 
-use crate::{routes::ZoomTokenResponse, ZoomState};
+    pub(crate) fn as_str(&self) -> &str {
+        &self.token
+    }
+}
+
+impl ZoomClient {
+    pub(crate) fn from_env() -> cja::Result<Self> {
+        let api_base_url =
+            std::env::var("ZOOM_API_BASE_URL").unwrap_or_else(|_| DEFAULT_API_BASE_URL.to_string());
+        let oauth_base_url = std::env::var("ZOOM_OAUTH_BASE_URL")
+            .unwrap_or_else(|_| DEFAULT_OAUTH_BASE_URL.to_string());
+
+        Self::new(api_base_url, oauth_base_url)
+    }
+
+    pub(crate) fn new(
+        api_base_url: impl Into<String>,
+        oauth_base_url: impl Into<String>,
+    ) -> cja::Result<Self> {
+        let http = Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to build Zoom HTTP client")?;
+
+        Ok(Self {
+            http,
+            api_base_url: api_base_url.into().trim_end_matches('/').to_string(),
+            oauth_base_url: oauth_base_url.into().trim_end_matches('/').to_string(),
+            rate_limiter: Default::default(),
+        })
+    }
+
+    fn api_url(&self, path: &str) -> String {
+        format!("{}/v2{path}", self.api_base_url)
+    }
+
+    fn oauth_url(&self, path: &str) -> String {
+        format!("{}/oauth{path}", self.oauth_base_url)
+    }
+
+    /// Unlike the rest of the OAuth endpoints, data compliance lives on the API host
+    fn data_compliance_url(&self) -> String {
+        format!("{}/oauth/data/compliance", self.api_base_url)
+    }
+
+    pub(crate) fn authorize_url(&self, client_id: &str, redirect_uri: &str) -> String {
+        format!(
+            "{}?response_type=code&client_id={client_id}&redirect_uri={redirect_uri}",
+            self.oauth_url("/authorize")
+        )
+    }
+
+    pub(crate) fn rate_limit_stats(&self) -> RateLimitSnapshot {
+        self.rate_limiter.stats()
+    }
+
+    /// Sends an authenticated API request, waiting for a rate limit slot first and
+    /// backing off the user and category when Zoom answers with a 429.
+    async fn send(
+        &self,
+        access_token: &AccessToken,
+        category: RateLimitCategory,
+        request: RequestBuilder,
+    ) -> Result<Response, ZoomError> {
+        self.rate_limiter
+            .acquire(access_token.user_id, category)
+            .await?;
+
+        let resp = request.bearer_auth(&access_token.token).send().await?;
+
+        if resp.status() == StatusCode::TOO_MANY_REQUESTS {
+            let error = ZoomError::from_response(resp).await;
+            if let ZoomError::RateLimited { retry_after } = &error {
+                self.rate_limiter
+                    .rate_limited(access_token.user_id, category, *retry_after);
+            }
+            return Err(error);
+        }
+
+        Self::check(resp).await
+    }
+
+    async fn check(resp: Response) -> Result<Response, ZoomError> {
+        if resp.status().is_success() {
+            Ok(resp)
+        } else {
+            Err(ZoomError::from_response(resp).await)
+        }
+    }
+
+    async fn parse<T: DeserializeOwned>(resp: Response) -> Result<T, ZoomError> {
+        let text = resp.text().await?;
+
+        Ok(serde_json::from_str(&text)?)
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct Meetings {
@@ -23,37 +179,43 @@ pub(crate) struct ListedMeeting {
     pub id: i64,
     pub start_time: Option<String>,
     pub timezone: Option<String>,
+    pub topic: Option<String>,
     pub r#type: i64,
     pub uuid: String,
 }
 
+#[derive(Serialize, Debug)]
+struct DataComplianceBody<'a> {
+    client_id: &'a str,
+    user_id: Option<&'a str>,
+    account_id: Option<&'a str>,
+    deauthorization_event_received: &'a serde_json::Value,
+    compliance_completed: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct UpdateMeetingStatusBody {
     action: String,
 }
 
-pub(crate) async fn adios(meeting_id: impl ToString, access_token: &str) -> cja::Result<()> {
-    let client = Client::new();
-    let url = format!(
-        "https://api.zoom.us/v2/meetings/{}/status",
-        meeting_id.to_string()
-    );
-    let body = UpdateMeetingStatusBody {
-        action: "end".to_string(),
-    };
-    let resp = client
-        .put(url)
-        .bearer_auth(access_token)
-        .json(&body)
-        .send()
+impl ZoomClient {
+    pub(crate) async fn adios(
+        &self,
+        meeting_id: impl ToString,
+        access_token: &AccessToken,
+    ) -> Result<(), ZoomError> {
+        let url = self.api_url(&format!("/meetings/{}/status", meeting_id.to_string()));
+        let body = UpdateMeetingStatusBody {
+            action: "end".to_string(),
+        };
+        self.send(
+            access_token,
+            RateLimitCategory::Light,
+            self.http.put(url).json(&body),
+        )
         .await?;
 
-    if resp.status().is_success() {
         Ok(())
-    } else {
-        let status = resp.status();
-        let text = resp.text().await?;
-        Err(eyre::eyre!("Failed to end meeting: {status} {text}"))
     }
 }
 
@@ -63,39 +225,49 @@ impl ListedMeeting {
             .context("Could not parse created at timestamp")
     }
 
-    pub(crate) fn live_duration(&self) -> cja::Result<i64> {
-        if self.r#type == 4 {
-            return Err(eyre::eyre!(
-                "Could not determine the live duration of a Personal Meeting Room Meeting because the API created_at is the first time the meeting was ever used and no start time is presented"
-            ));
-        }
+    /// Instant meetings are created the moment they start, so `created_at` is a good
+    /// stand in for the start time. Every other type is created ahead of time.
+    pub(crate) fn instant_meeting_start_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
         if self.r#type != 1 {
-            return Err(eyre::eyre!("Meeting type {} is not supported", self.r#type));
+            return None;
         }
-        let created_at = self.created_at()?;
-        let now = chrono::Utc::now().naive_utc();
-        let duration = now - created_at;
-        Ok(duration.num_seconds())
+
+        Some(self.created_at().ok()?.and_utc())
+    }
+
+    /// Scheduled, recurring and Personal Meeting Room meetings can all be started
+    /// more than once under the same meeting id, so `created_at` tells us nothing
+    /// about this session
+    pub(crate) fn reuses_meeting_id(&self) -> bool {
+        matches!(self.r#type, 2 | 3 | 4 | 8)
+    }
+
+    pub(crate) fn is_recurring(&self) -> bool {
+        matches!(self.r#type, 3 | 8)
     }
 }
 
+/// A meeting as returned by `GET /meetings/{meetingId}`
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct Meeting {
     pub id: i64,
     pub r#type: i64,
-    pub start_time: Option<chrono::NaiveDateTime>,
+    pub topic: Option<String>,
+    pub start_time: Option<chrono::DateTime<chrono::Utc>>,
     pub duration: Option<i64>,
+    pub timezone: Option<String>,
+    pub agenda: Option<String>,
     pub occurrences: Option<Vec<MeetingOccurrence>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct MeetingOccurrence {
     pub occurrence_id: String,
-    pub start_time: chrono::NaiveDateTime,
+    pub start_time: chrono::DateTime<chrono::Utc>,
     pub duration: i64,
 }
 
-#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
 pub(crate) enum MeetingType {
     Live,
     Scheduled,
@@ -110,103 +282,339 @@ impl MeetingType {
     }
 }
 
-pub(crate) async fn get_meetings(
-    access_token: &str,
-    meeting_type: MeetingType,
-) -> cja::Result<Meetings> {
-    let client = Client::new();
-    let resp = client
-        .get("https://api.zoom.us/v2/users/me/meetings")
-        .query(&[("type", meeting_type.query_param())])
-        .header("Authorization", format!("Bearer {}", access_token))
-        .send()
-        .await?;
+impl ZoomClient {
+    pub(crate) async fn get_meetings_page(
+        &self,
+        access_token: &AccessToken,
+        meeting_type: MeetingType,
+        next_page_token: Option<&str>,
+    ) -> Result<Meetings, ZoomError> {
+        let mut query = vec![
+            ("type", meeting_type.query_param()),
+            ("page_size", MEETINGS_PAGE_SIZE),
+        ];
+        if let Some(next_page_token) = next_page_token {
+            query.push(("next_page_token", next_page_token));
+        }
+
+        let resp = self
+            .send(
+                access_token,
+                RateLimitCategory::Medium,
+                self.http
+                    .get(self.api_url("/users/me/meetings"))
+                    .query(&query),
+            )
+            .await?;
+
+        let resp_text = resp.text().await?;
+        tracing::debug!(response = %resp_text, "Listed meetings page");
 
-    let resp_text = resp.text().await?;
-    dbg!(&resp_text);
-
-    Ok(serde_json::from_str(&resp_text)?)
-}
-
-pub(crate) async fn refresh_access_token(
-    zoom_state: &ZoomState,
-    refresh_token: &str,
-) -> cja::Result<ZoomTokenResponse> {
-    let client = Client::new();
-    let access_token_response = client
-        .post("https://zoom.us/oauth/token")
-        .basic_auth(&zoom_state.client_id, Some(&zoom_state.client_secret))
-        .form(&[
-            ("grant_type", "refresh_token"),
-            ("refresh_token", refresh_token),
-        ])
-        .send()
+        Ok(serde_json::from_str(&resp_text)?)
+    }
+
+    pub(crate) async fn exchange_code(
+        &self,
+        zoom_state: &ZoomState,
+        code: &str,
+        redirect_uri: &str,
+    ) -> Result<ZoomTokenResponse, ZoomError> {
+        let access_token_response = self
+            .http
+            .post(self.oauth_url("/token"))
+            .basic_auth(&zoom_state.client_id, Some(&zoom_state.client_secret))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri),
+            ])
+            .send()
+            .await?;
+
+        Self::parse(Self::check(access_token_response).await?).await
+    }
+
+    pub(crate) async fn refresh_access_token(
+        &self,
+        zoom_state: &ZoomState,
+        refresh_token: &str,
+    ) -> Result<ZoomTokenResponse, ZoomError> {
+        let access_token_response = self
+            .http
+            .post(self.oauth_url("/token"))
+            .basic_auth(&zoom_state.client_id, Some(&zoom_state.client_secret))
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ])
+            .send()
+            .await?;
+
+        Self::parse(Self::check(access_token_response).await?).await
+    }
+
+    /// Tells Zoom we deleted everything we had for a user that removed the app.
+    /// Marketplace apps must do this after every `app_deauthorized` webhook.
+    pub(crate) async fn acknowledge_data_compliance(
+        &self,
+        zoom_state: &ZoomState,
+        deauthorization_event: &serde_json::Value,
+    ) -> Result<(), ZoomError> {
+        let body = DataComplianceBody {
+            client_id: &zoom_state.client_id,
+            user_id: deauthorization_event["user_id"].as_str(),
+            account_id: deauthorization_event["account_id"].as_str(),
+            deauthorization_event_received: deauthorization_event,
+            compliance_completed: true,
+        };
+
+        let resp = self
+            .http
+            .post(self.data_compliance_url())
+            .basic_auth(&zoom_state.client_id, Some(&zoom_state.client_secret))
+            .json(&body)
+            .send()
+            .await?;
+        Self::check(resp).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn get_current_user(
+        &self,
+        access_token: &AccessToken,
+    ) -> Result<ZoomUser, ZoomError> {
+        let user_response = self
+            .send(
+                access_token,
+                RateLimitCategory::Light,
+                self.http.get(self.api_url("/users/me")),
+            )
+            .await?;
+
+        Self::parse(user_response).await
+    }
+
+    #[allow(dead_code)]
+    pub(crate) async fn send_chat_message(
+        &self,
+        access_token: &AccessToken,
+        meeting_id: &str,
+        message: &str,
+    ) -> Result<(), ZoomError> {
+        let to_channel = format!("meeting_{meeting_id}");
+        let body = ChatMessage {
+            message: message.to_string(),
+            to_channel,
+        };
+
+        self.send(
+            access_token,
+            RateLimitCategory::Medium,
+            self.http
+                .post(self.api_url("/chat/users/me/messages"))
+                .json(&body),
+        )
         .await?;
 
-    let token_response_text = access_token_response.text().await?;
+        Ok(())
+    }
+
+    pub(crate) async fn get_chat_channels(
+        &self,
+        access_token: &AccessToken,
+    ) -> Result<serde_json::Value, ZoomError> {
+        let resp = self
+            .send(
+                access_token,
+                RateLimitCategory::Medium,
+                self.http.get(self.api_url("/chat/users/me/channels")),
+            )
+            .await?;
+
+        Self::parse(resp).await
+    }
+
+    pub(crate) async fn get_meeting_details(
+        &self,
+        access_token: &AccessToken,
+        meeting_id: &str,
+    ) -> Result<Meeting, ZoomError> {
+        let resp = self
+            .send(
+                access_token,
+                RateLimitCategory::Light,
+                self.http
+                    .get(self.api_url(&format!("/meetings/{meeting_id}"))),
+            )
+            .await?;
+
+        Self::parse(resp).await
+    }
+
+    pub(crate) async fn get_past_meeting_instances(
+        &self,
+        access_token: &AccessToken,
+        meeting_id: &str,
+    ) -> Result<PastMeetingInstances, ZoomError> {
+        let resp = self
+            .send(
+                access_token,
+                RateLimitCategory::Medium,
+                self.http
+                    .get(self.api_url(&format!("/past_meetings/{meeting_id}/instances"))),
+            )
+            .await?;
+
+        Self::parse(resp).await
+    }
+
+    /// Needs a Business plan or higher, other accounts get an error back
+    pub(crate) async fn get_live_meeting_metrics(
+        &self,
+        access_token: &AccessToken,
+        meeting_uuid: &str,
+    ) -> Result<MeetingMetrics, ZoomError> {
+        let resp = self
+            .send(
+                access_token,
+                RateLimitCategory::Heavy,
+                self.http
+                    .get(self.api_url(&format!(
+                        "/metrics/meetings/{}",
+                        encode_meeting_uuid(meeting_uuid)
+                    )))
+                    .query(&[("type", "live")]),
+            )
+            .await?;
+
+        Self::parse(resp).await
+    }
+
+    pub(crate) async fn get_past_meeting(
+        &self,
+        access_token: &AccessToken,
+        meeting_uuid: &str,
+    ) -> Result<PastMeeting, ZoomError> {
+        let resp = self
+            .send(
+                access_token,
+                RateLimitCategory::Light,
+                self.http.get(self.api_url(&format!(
+                    "/past_meetings/{}",
+                    encode_meeting_uuid(meeting_uuid)
+                ))),
+            )
+            .await?;
 
-    let token_response: ZoomTokenResponse = serde_json::from_str(&token_response_text)?;
+        Self::parse(resp).await
+    }
+}
 
-    Ok(token_response)
+/// Live stats for a running meeting from the Dashboard API, we only need its
+/// actual start time
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct MeetingMetrics {
+    pub uuid: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
 }
 
+/// Every instance a meeting has had, most useful for Personal Meeting Rooms and
+/// recurring meetings which share one meeting id
 #[derive(Serialize, Deserialize, Debug, Clone)]
-struct ChatMessage {
-    message: String,
-    to_channel: String,
+pub(crate) struct PastMeetingInstances {
+    pub meetings: Vec<PastMeetingInstance>,
 }
 
-#[allow(dead_code)]
-pub(crate) async fn send_chat_message(
-    access_token: &str,
-    meeting_id: &str,
-    message: &str,
-) -> cja::Result<()> {
-    let client = Client::new();
-    let url = "https://api.zoom.us/v2/chat/users/me/messages";
-    let to_channel = format!("meeting_{meeting_id}");
-    let body = ChatMessage {
-        message: message.to_string(),
-        to_channel,
-    };
-
-    let resp = client
-        .post(url)
-        .bearer_auth(access_token)
-        .json(&body)
-        .send()
-        .await?;
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct PastMeetingInstance {
+    pub uuid: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+}
 
-    if resp.status().is_success() {
-        Ok(())
+/// Meeting UUIDs are base64 and can contain `/`. Zoom wants them encoded, and
+/// encoded twice if they start with `/` or contain `//`.
+fn encode_meeting_uuid(uuid: &str) -> String {
+    fn percent_encode(value: &str) -> String {
+        value
+            .bytes()
+            .map(|b| {
+                if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+                    (b as char).to_string()
+                } else {
+                    format!("%{b:02X}")
+                }
+            })
+            .collect()
+    }
+
+    let encoded = percent_encode(uuid);
+    if uuid.starts_with('/') || uuid.contains("//") {
+        percent_encode(&encoded)
     } else {
-        let status = resp.status();
-        let text = resp.text().await?;
-        Err(eyre::eyre!("Failed to send chat message: {status} {text}"))
+        encoded
     }
 }
 
-#[allow(dead_code)]
-pub(crate) async fn get_chat_channels(access_token: &str) -> cja::Result<serde_json::Value> {
-    let client = Client::new();
-    let url = "https://api.zoom.us/v2/chat/users/me/channels";
-    let resp = client.get(url).bearer_auth(access_token).send().await?;
+/// A single instance of a meeting that has already ended
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct PastMeeting {
+    pub uuid: String,
+    pub start_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub participants_count: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ZoomUser {
+    pub(crate) id: String,
+    pub(crate) display_name: String,
+    pub(crate) pic_url: Option<String>,
+    pub(crate) email: Option<String>,
+    pub(crate) timezone: Option<String>,
+    #[serde(rename = "type")]
+    pub(crate) plan_type: Option<i32>,
+}
 
-    let json = resp.json().await?;
+impl From<ZoomUser> for ZoomProfileUpdate {
+    fn from(user: ZoomUser) -> Self {
+        Self {
+            display_name: Some(user.display_name),
+            pic_url: user.pic_url,
+            email: user.email,
+            timezone: user.timezone,
+            plan_type: user.plan_type,
+        }
+    }
+}
 
-    Ok(json)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ChatMessage {
+    message: String,
+    to_channel: String,
 }
 
-#[allow(dead_code)]
-pub(crate) async fn get_meeting_details(
-    access_token: &str,
-    meeting_id: &str,
-) -> cja::Result<serde_json::Value> {
-    let client = Client::new();
-    let url = format!("https://api.zoom.us/v2/meetings/{meeting_id}");
-    let resp = client.get(url).bearer_auth(access_token).send().await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let json = resp.json().await?;
+    #[test]
+    fn data_compliance_is_sent_to_the_api_host() {
+        let client = ZoomClient::new(DEFAULT_API_BASE_URL, DEFAULT_OAUTH_BASE_URL).unwrap();
 
-    Ok(json)
+        assert_eq!(
+            client.data_compliance_url(),
+            "https://api.zoom.us/oauth/data/compliance"
+        );
+    }
+
+    #[test]
+    fn data_compliance_follows_the_api_base_url_override() {
+        let client = ZoomClient::new("http://localhost:9000/", "http://localhost:9001").unwrap();
+
+        assert_eq!(
+            client.data_compliance_url(),
+            "http://localhost:9000/oauth/data/compliance"
+        );
+    }
 }
@@ -0,0 +1,384 @@
+use std::{fmt, time::Duration};
+
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+use serde::Deserialize;
+
+/// Zoom's `code` for an access token it no longer accepts
+const INVALID_ACCESS_TOKEN_CODE: i64 = 124;
+/// Zoom's `code` for too many requests, sometimes sent without a 429 status
+const RATE_LIMITED_CODE: i64 = 429;
+/// Zoom's `code` for acting on a meeting that isn't running
+const MEETING_NOT_STARTED_CODE: i64 = 3000;
+/// Zoom's `code` for "Meeting does not exist"
+const MEETING_NOT_FOUND_CODE: i64 = 3001;
+
+/// The error body Zoom sends back. The REST API uses `code` and `message` while the
+/// OAuth endpoints use `error` and `reason`.
+#[derive(Debug, Deserialize, Default)]
+struct ZoomErrorBody {
+    code: Option<i64>,
+    message: Option<String>,
+    error: Option<String>,
+    reason: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) enum ZoomError {
+    /// The access token was rejected, refreshing it should fix things
+    TokenExpired {
+        message: String,
+    },
+    /// The refresh token was rejected, the user needs to log in with Zoom again
+    TokenRevoked {
+        message: String,
+    },
+    MeetingNotFound {
+        message: String,
+    },
+    MeetingNotInProgress {
+        message: String,
+    },
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    Server {
+        status: StatusCode,
+        message: String,
+    },
+    Api {
+        status: StatusCode,
+        code: Option<i64>,
+        message: String,
+    },
+    Http(reqwest::Error),
+    Decode(serde_json::Error),
+}
+
+impl ZoomError {
+    pub(crate) async fn from_response(resp: Response) -> Self {
+        let status = resp.status();
+        let retry_after = retry_after(&resp);
+        let text = resp.text().await.unwrap_or_default();
+        let body: ZoomErrorBody = serde_json::from_str(&text).unwrap_or_default();
+
+        Self::classify(status, retry_after, body, text)
+    }
+
+    fn classify(
+        status: StatusCode,
+        retry_after: Option<Duration>,
+        body: ZoomErrorBody,
+        text: String,
+    ) -> Self {
+        let message = body
+            .message
+            .or(body.reason)
+            .unwrap_or(text)
+            .trim()
+            .to_string();
+
+        // Zoom's codes are stable, the wording of its messages isn't
+        match body.code {
+            Some(RATE_LIMITED_CODE) => return Self::RateLimited { retry_after },
+            Some(INVALID_ACCESS_TOKEN_CODE) => return Self::TokenExpired { message },
+            Some(MEETING_NOT_STARTED_CODE) => return Self::MeetingNotInProgress { message },
+            Some(MEETING_NOT_FOUND_CODE) => return Self::MeetingNotFound { message },
+            _ => {}
+        }
+
+        if body.error.as_deref() == Some("invalid_grant") {
+            return Self::TokenRevoked { message };
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Self::RateLimited { retry_after };
+        }
+
+        if status.is_server_error() {
+            return Self::Server { status, message };
+        }
+
+        // Fall back to the message for responses without a code we know
+        if message.contains("Invalid Token") {
+            return Self::TokenRevoked { message };
+        }
+
+        if status == StatusCode::UNAUTHORIZED {
+            return Self::TokenExpired { message };
+        }
+
+        let lowercase_message = message.to_lowercase();
+        if lowercase_message.contains("not started")
+            || lowercase_message.contains("not in progress")
+        {
+            return Self::MeetingNotInProgress { message };
+        }
+
+        Self::Api {
+            status,
+            code: body.code,
+            message,
+        }
+    }
+
+    /// Whether trying the exact same request again later could succeed
+    pub(crate) fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } | Self::Server { .. } => true,
+            Self::Http(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        }
+    }
+}
+
+/// `Retry-After` is either a number of seconds or, for Zoom's daily limits, the
+/// time the limit resets.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let value = resp.headers().get(RETRY_AFTER)?.to_str().ok()?;
+
+    parse_retry_after(value, chrono::Utc::now())
+}
+
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let retry_at = chrono::DateTime::parse_from_rfc3339(value)
+        .or_else(|_| chrono::DateTime::parse_from_rfc2822(value))
+        .ok()?;
+
+    (retry_at.to_utc() - now).to_std().ok()
+}
+
+impl fmt::Display for ZoomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TokenExpired { message } => write!(f, "Zoom access token expired: {message}"),
+            Self::TokenRevoked { message } => write!(f, "Zoom token revoked: {message}"),
+            Self::MeetingNotFound { message } => write!(f, "Zoom meeting not found: {message}"),
+            Self::MeetingNotInProgress { message } => {
+                write!(f, "Zoom meeting not in progress: {message}")
+            }
+            Self::RateLimited {
+                retry_after: Some(retry_after),
+            } => write!(f, "Zoom rate limit hit, retry after {retry_after:?}"),
+            Self::RateLimited { retry_after: None } => write!(f, "Zoom rate limit hit"),
+            Self::Server { status, message } => write!(f, "Zoom server error: {status} {message}"),
+            Self::Api {
+                status,
+                code: Some(code),
+                message,
+            } => write!(f, "Zoom API error: {status} ({code}) {message}"),
+            Self::Api {
+                status,
+                code: None,
+                message,
+            } => write!(f, "Zoom API error: {status} {message}"),
+            Self::Http(e) => write!(f, "Zoom request failed: {e}"),
+            Self::Decode(e) => write!(f, "Could not decode Zoom response: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ZoomError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ZoomError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Http(e)
+    }
+}
+
+impl From<serde_json::Error> for ZoomError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Decode(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(status: u16, text: &str) -> ZoomError {
+        classify_with_retry_after(status, None, text)
+    }
+
+    fn classify_with_retry_after(
+        status: u16,
+        retry_after: Option<Duration>,
+        text: &str,
+    ) -> ZoomError {
+        ZoomError::classify(
+            StatusCode::from_u16(status).unwrap(),
+            retry_after,
+            serde_json::from_str(text).unwrap_or_default(),
+            text.to_string(),
+        )
+    }
+
+    #[test]
+    fn classifies_by_code_before_message() {
+        assert!(matches!(
+            classify(401, r#"{"code":124,"message":"Invalid access token."}"#),
+            ZoomError::TokenExpired { .. }
+        ));
+        assert!(matches!(
+            classify(404, r#"{"code":3001,"message":"Some new wording"}"#),
+            ZoomError::MeetingNotFound { .. }
+        ));
+        assert!(matches!(
+            classify(400, r#"{"code":3000,"message":"Some new wording"}"#),
+            ZoomError::MeetingNotInProgress { .. }
+        ));
+        assert!(matches!(
+            classify(400, r#"{"code":429,"message":"Some new wording"}"#),
+            ZoomError::RateLimited { .. }
+        ));
+    }
+
+    #[test]
+    fn known_codes_win_over_misleading_messages() {
+        assert!(matches!(
+            classify(
+                404,
+                r#"{"code":3001,"message":"Meeting not started or Invalid Token"}"#
+            ),
+            ZoomError::MeetingNotFound { .. }
+        ));
+    }
+
+    #[test]
+    fn classifies_revoked_refresh_tokens() {
+        assert!(matches!(
+            classify(
+                400,
+                r#"{"reason":"Invalid Token!","error":"invalid_grant"}"#
+            ),
+            ZoomError::TokenRevoked { .. }
+        ));
+        assert!(matches!(
+            classify(400, r#"{"reason":"Invalid Token!"}"#),
+            ZoomError::TokenRevoked { .. }
+        ));
+    }
+
+    #[test]
+    fn classifies_unauthorized_without_a_code_as_expired() {
+        assert!(matches!(
+            classify(401, r#"{"message":"Unauthorized"}"#),
+            ZoomError::TokenExpired { .. }
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_the_message_for_meetings_not_running() {
+        assert!(matches!(
+            classify(400, r#"{"message":"Meeting is not started yet."}"#),
+            ZoomError::MeetingNotInProgress { .. }
+        ));
+        assert!(matches!(
+            classify(400, r#"{"message":"Meeting Not In Progress"}"#),
+            ZoomError::MeetingNotInProgress { .. }
+        ));
+    }
+
+    #[test]
+    fn classifies_rate_limits_with_retry_after() {
+        let error = classify_with_retry_after(429, Some(Duration::from_secs(7)), "");
+
+        assert!(matches!(
+            error,
+            ZoomError::RateLimited {
+                retry_after: Some(retry_after)
+            } if retry_after == Duration::from_secs(7)
+        ));
+    }
+
+    #[test]
+    fn classifies_server_errors_with_the_raw_text() {
+        let error = classify(503, "Service Unavailable");
+
+        assert!(matches!(
+            error,
+            ZoomError::Server { status, message }
+                if status == StatusCode::SERVICE_UNAVAILABLE && message == "Service Unavailable"
+        ));
+    }
+
+    #[test]
+    fn keeps_unknown_codes_as_api_errors() {
+        let error = classify(400, r#"{"code":300,"message":"Invalid meeting id."}"#);
+
+        assert!(matches!(
+            error,
+            ZoomError::Api {
+                status: StatusCode::BAD_REQUEST,
+                code: Some(300),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn only_rate_limits_and_server_errors_are_retryable() {
+        assert!(classify(429, "").is_retryable());
+        assert!(classify(500, "").is_retryable());
+
+        for error in [
+            classify(401, r#"{"code":124}"#),
+            classify(400, r#"{"error":"invalid_grant"}"#),
+            classify(404, r#"{"code":3001}"#),
+            classify(400, r#"{"code":3000}"#),
+            classify(400, r#"{"code":300}"#),
+            ZoomError::Decode(serde_json::from_str::<i64>("nope").unwrap_err()),
+        ] {
+            assert!(!error.is_retryable(), "{error} should not be retryable");
+        }
+    }
+
+    #[test]
+    fn parses_retry_after_seconds() {
+        let now = chrono::Utc::now();
+
+        assert_eq!(
+            parse_retry_after(" 30 ", now),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn parses_retry_after_dates() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-10-20T12:00:00Z")
+            .unwrap()
+            .to_utc();
+
+        assert_eq!(
+            parse_retry_after("2024-10-20T12:01:00Z", now),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 20 Oct 2024 12:02:00 GMT", now),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn ignores_retry_after_in_the_past_or_unparseable() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-10-20T12:00:00Z")
+            .unwrap()
+            .to_utc();
+
+        assert_eq!(parse_retry_after("2024-10-20T11:59:00Z", now), None);
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
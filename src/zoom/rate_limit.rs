@@ -0,0 +1,145 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use cja::uuid::Uuid;
+use tokio::time::Instant;
+
+use super::ZoomError;
+
+/// How long we are willing to sleep for a slot before handing the caller a
+/// [`ZoomError::RateLimited`] so it can reschedule itself instead.
+const MAX_WAIT: Duration = Duration::from_secs(10);
+
+/// Used when Zoom sends a 429 without a `Retry-After` header
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Zoom groups every endpoint into a rate limit category, each with its own
+/// requests-per-second budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum RateLimitCategory {
+    Light,
+    Medium,
+    Heavy,
+}
+
+impl RateLimitCategory {
+    /// Spacing between requests that keeps us under the Pro plan limits of
+    /// 30 / 20 / 10 requests per second.
+    fn min_interval(self) -> Duration {
+        match self {
+            Self::Light => Duration::from_millis(34),
+            Self::Medium => Duration::from_millis(50),
+            Self::Heavy => Duration::from_millis(100),
+        }
+    }
+}
+
+/// Tracks when each user may next call each category of endpoint.
+///
+/// Requests without a user (like fetching the profile during the OAuth login)
+/// share a single bucket.
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+    next_allowed: Mutex<HashMap<(Option<Uuid>, RateLimitCategory), Instant>>,
+    stats: RateLimitStats,
+}
+
+impl RateLimiter {
+    /// Waits for the next free slot, or fails straight away if that slot is too far out
+    pub(crate) async fn acquire(
+        &self,
+        user_id: Option<Uuid>,
+        category: RateLimitCategory,
+    ) -> Result<(), ZoomError> {
+        self.stats.requests.fetch_add(1, Ordering::Relaxed);
+
+        let now = Instant::now();
+        let slot = {
+            let mut next_allowed = self
+                .next_allowed
+                .lock()
+                .expect("Rate limiter lock poisoned");
+            next_allowed.retain(|_, next| *next > now);
+
+            let slot = next_allowed
+                .get(&(user_id, category))
+                .copied()
+                .unwrap_or(now)
+                .max(now);
+            if slot - now > MAX_WAIT {
+                self.stats.rejected.fetch_add(1, Ordering::Relaxed);
+                return Err(ZoomError::RateLimited {
+                    retry_after: Some(slot - now),
+                });
+            }
+
+            next_allowed.insert((user_id, category), slot + category.min_interval());
+            slot
+        };
+
+        if slot > now {
+            self.stats.throttled.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep_until(slot).await;
+        }
+
+        Ok(())
+    }
+
+    /// Blocks the user and category until Zoom says we can try again
+    pub(crate) fn rate_limited(
+        &self,
+        user_id: Option<Uuid>,
+        category: RateLimitCategory,
+        retry_after: Option<Duration>,
+    ) {
+        self.stats.rate_limited.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(
+            ?user_id,
+            ?category,
+            ?retry_after,
+            "Zoom rate limit hit, backing off"
+        );
+
+        let blocked_until = Instant::now() + retry_after.unwrap_or(DEFAULT_RETRY_AFTER);
+        self.next_allowed
+            .lock()
+            .expect("Rate limiter lock poisoned")
+            .insert((user_id, category), blocked_until);
+    }
+
+    pub(crate) fn stats(&self) -> RateLimitSnapshot {
+        RateLimitSnapshot {
+            requests: self.stats.requests.load(Ordering::Relaxed),
+            throttled: self.stats.throttled.load(Ordering::Relaxed),
+            rejected: self.stats.rejected.load(Ordering::Relaxed),
+            rate_limited: self.stats.rate_limited.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct RateLimitStats {
+    requests: AtomicU64,
+    throttled: AtomicU64,
+    rejected: AtomicU64,
+    rate_limited: AtomicU64,
+}
+
+/// Counters since the process started
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RateLimitSnapshot {
+    /// Requests that went through the limiter
+    pub(crate) requests: u64,
+    /// Requests we delayed to stay under the limit
+    pub(crate) throttled: u64,
+    /// Requests we refused locally because the wait would have been too long
+    pub(crate) rejected: u64,
+    /// 429 responses we got back from Zoom
+    pub(crate) rate_limited: u64,
+}
@@ -0,0 +1,201 @@
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use futures::{Stream, TryStreamExt as _};
+
+use crate::{db::DBUser, AppState};
+
+use super::{
+    AccessToken, ListedMeeting, Meeting, MeetingMetrics, MeetingType, PastMeeting,
+    PastMeetingInstances, ZoomClient, ZoomError, ZoomUser,
+};
+
+/// Makes Zoom API calls on behalf of a stored user.
+///
+/// Every call gets a fresh access token from [`DBUser::access_token`], and when Zoom
+/// rejects it anyway we refresh and retry the call once.
+pub(crate) struct UserZoomClient<'a> {
+    app_state: &'a AppState,
+    user: &'a DBUser,
+}
+
+impl<'a> UserZoomClient<'a> {
+    pub(crate) fn new(app_state: &'a AppState, user: &'a DBUser) -> Self {
+        Self { app_state, user }
+    }
+
+    async fn call<T, F, Fut>(&self, request: F) -> cja::Result<T>
+    where
+        F: Fn(&'a ZoomClient, AccessToken) -> Fut,
+        Fut: Future<Output = Result<T, ZoomError>>,
+    {
+        let client = &self.app_state.zoom_client;
+        let access_token = self.user.access_token(self.app_state).await?;
+
+        match request(client, access_token.clone()).await {
+            Err(ZoomError::TokenExpired { message }) => {
+                tracing::info!(
+                    user_id = %self.user.user_id,
+                    "Zoom rejected the access token, refreshing and retrying: {message}"
+                );
+
+                let access_token = self
+                    .user
+                    .refresh_rejected_access_token(self.app_state, &access_token)
+                    .await?;
+
+                Ok(request(client, access_token).await?)
+            }
+            result => Ok(result?),
+        }
+    }
+
+    pub(crate) async fn adios(&self, meeting_id: &str) -> cja::Result<()> {
+        self.call(
+            |client, access_token| async move { client.adios(meeting_id, &access_token).await },
+        )
+        .await
+    }
+
+    /// Streams every meeting of the given type, following `next_page_token` until
+    /// Zoom stops returning one.
+    pub(crate) fn get_meetings(
+        &self,
+        meeting_type: MeetingType,
+    ) -> impl Stream<Item = cja::Result<ListedMeeting>> + '_ {
+        // `None` means we are done, `Some(None)` means we still need the first page
+        let first_page: Option<Option<String>> = Some(None);
+
+        futures::stream::try_unfold(first_page, move |page_token| async move {
+            let Some(page_token) = page_token else {
+                return Ok::<_, eyre::Report>(None);
+            };
+
+            let page = self
+                .call(|client, access_token| {
+                    let page_token = page_token.as_deref();
+                    async move {
+                        client
+                            .get_meetings_page(&access_token, meeting_type, page_token)
+                            .await
+                    }
+                })
+                .await?;
+
+            let next_page_token = page
+                .next_page_token
+                .filter(|token| !token.is_empty())
+                .map(Some);
+            let meetings = futures::stream::iter(page.meetings.into_iter().map(Ok));
+
+            Ok(Some((meetings, next_page_token)))
+        })
+        .try_flatten()
+    }
+
+    pub(crate) async fn get_current_user(&self) -> cja::Result<ZoomUser> {
+        self.call(
+            |client, access_token| async move { client.get_current_user(&access_token).await },
+        )
+        .await
+    }
+
+    #[allow(dead_code)]
+    pub(crate) async fn send_chat_message(
+        &self,
+        meeting_id: &str,
+        message: &str,
+    ) -> cja::Result<()> {
+        self.call(|client, access_token| async move {
+            client
+                .send_chat_message(&access_token, meeting_id, message)
+                .await
+        })
+        .await
+    }
+
+    pub(crate) async fn get_chat_channels(&self) -> cja::Result<serde_json::Value> {
+        self.call(
+            |client, access_token| async move { client.get_chat_channels(&access_token).await },
+        )
+        .await
+    }
+
+    pub(crate) async fn get_meeting_details(&self, meeting_id: &str) -> cja::Result<Meeting> {
+        self.call(|client, access_token| async move {
+            client.get_meeting_details(&access_token, meeting_id).await
+        })
+        .await
+    }
+
+    pub(crate) async fn get_past_meeting(&self, meeting_uuid: &str) -> cja::Result<PastMeeting> {
+        self.call(|client, access_token| async move {
+            client.get_past_meeting(&access_token, meeting_uuid).await
+        })
+        .await
+    }
+
+    pub(crate) async fn get_live_meeting_metrics(
+        &self,
+        meeting_uuid: &str,
+    ) -> cja::Result<MeetingMetrics> {
+        self.call(|client, access_token| async move {
+            client
+                .get_live_meeting_metrics(&access_token, meeting_uuid)
+                .await
+        })
+        .await
+    }
+
+    pub(crate) async fn get_past_meeting_instances(
+        &self,
+        meeting_id: &str,
+    ) -> cja::Result<PastMeetingInstances> {
+        self.call(|client, access_token| async move {
+            client
+                .get_past_meeting_instances(&access_token, meeting_id)
+                .await
+        })
+        .await
+    }
+
+    /// When a live meeting actually started, or `None` if Zoom won't tell us.
+    ///
+    /// The Dashboard API knows, but only for Business plans and up. Otherwise
+    /// instant meetings start when they are created, and any meeting that can be
+    /// started more than once lists the current session among its instances.
+    pub(crate) async fn live_meeting_start_time(
+        &self,
+        meeting: &ListedMeeting,
+    ) -> cja::Result<Option<DateTime<Utc>>> {
+        match self.get_live_meeting_metrics(&meeting.uuid).await {
+            Ok(metrics) => return Ok(Some(metrics.start_time)),
+            Err(e) if is_rate_limited(&e) => return Err(e),
+            Err(e) => tracing::debug!("Could not get meeting metrics: {e}"),
+        }
+
+        if meeting.reuses_meeting_id() {
+            return match self
+                .get_past_meeting_instances(&meeting.id.to_string())
+                .await
+            {
+                Ok(instances) => Ok(instances
+                    .meetings
+                    .into_iter()
+                    .find(|instance| instance.uuid == meeting.uuid)
+                    .map(|instance| instance.start_time)),
+                Err(e) if is_rate_limited(&e) => Err(e),
+                Err(e) => {
+                    tracing::debug!("Could not get past meeting instances: {e}");
+                    Ok(None)
+                }
+            };
+        }
+
+        Ok(meeting.instant_meeting_start_time())
+    }
+}
+
+fn is_rate_limited(e: &eyre::Report) -> bool {
+    matches!(e.downcast_ref(), Some(ZoomError::RateLimited { .. }))
+}